@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Owned} from "solmate/auth/Owned.sol";
+import {SafeTransferLib} from "solmate/utils/SafeTransferLib.sol";
+
+/// @notice GauloiFactory HTLC that also lets the buyer claim through a relayer, signing an authorization that pays
+/// the relayer a fee out of the swap so the buyer doesn't need any ETH for gas
+contract GauloiRelayFactory is Owned(msg.sender) {
+    using SafeTransferLib for address;
+
+    bytes32 public constant RELAYED_CLAIM_TYPEHASH =
+        keccak256("RelayedClaim(uint256 swapId,uint256 fee,address relayer)");
+
+    uint256 public swap_id = 0;
+    bool public is_active = true;
+
+    struct Swap {
+        address buyer;
+        address seller;
+        uint256 value;
+        bytes32 preimage_hash;
+        bytes32 preimage;
+        uint256 unlock_block;
+        bool complete;
+    }
+
+    mapping(uint256 => Swap) public swaps;
+    mapping(bytes32 => uint256) public hash_to_swap_map;
+
+    modifier onlyActive() {
+        require(is_active, "creating swaps isn't active");
+        _;
+    }
+
+    function set_active(bool new_active) external onlyOwner {
+        is_active = new_active;
+    }
+
+    function getSwap(uint256 swapId) external view returns (Swap memory) {
+        return swaps[swapId];
+    }
+
+    function domainSeparator() public view returns (bytes32) {
+        return keccak256(
+            abi.encode(
+                keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"),
+                keccak256("GauloiRelayFactory"),
+                keccak256("1"),
+                block.chainid,
+                address(this)
+            )
+        );
+    }
+
+    function createSwap(address buyer, bytes32 preimageHash, uint256 unlock_blocks) external payable onlyActive {
+        require(msg.value > 0, "creating a swap without any ETH");
+        uint256 next_swap_id = ++swap_id;
+        swaps[next_swap_id] =
+            Swap(buyer, msg.sender, msg.value, preimageHash, bytes32(0x0), block.number + unlock_blocks, false);
+        hash_to_swap_map[preimageHash] = next_swap_id;
+    }
+
+    function claimSwap(uint256 swapId, bytes32 preimage) external {
+        Swap storage swap = _reveal(swapId, preimage);
+        swap.buyer.safeTransferETH(swap.value);
+    }
+
+    /// @notice Claim for the buyer with their signed authorization, paying `fee` to the relayer. A zero `relayer`
+    /// lets anyone submit it, otherwise only that address can
+    function claimSwapRelayed(
+        uint256 swapId,
+        bytes32 preimage,
+        uint256 fee,
+        address relayer,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external {
+        require(relayer == address(0) || relayer == msg.sender, "claim wasn't sent by the relayer");
+        Swap storage swap = _reveal(swapId, preimage);
+        require(fee <= swap.value, "relayer fee is more than the swap");
+
+        bytes32 digest = keccak256(
+            abi.encodePacked(
+                "\x19\x01", domainSeparator(), keccak256(abi.encode(RELAYED_CLAIM_TYPEHASH, swapId, fee, relayer))
+            )
+        );
+        address signer = ecrecover(digest, v, r, s);
+        require(signer != address(0) && signer == swap.buyer, "claim wasn't authorized by the buyer");
+
+        swap.buyer.safeTransferETH(swap.value - fee);
+        msg.sender.safeTransferETH(fee);
+    }
+
+    function claimTimeout(uint256 swapId) external {
+        require(swapId > 0 && swapId <= swap_id, "swapId out of bounds");
+        Swap storage swap = swaps[swapId];
+        require(swap.unlock_block <= block.number, "swap not ready to claim");
+        require(!swap.complete, "swap is already completed");
+        swap.complete = true;
+        swap.seller.safeTransferETH(swap.value);
+    }
+
+    function _reveal(uint256 swapId, bytes32 preimage) internal returns (Swap storage swap) {
+        require(swapId > 0 && swapId <= swap_id, "swapId out of bounds");
+        swap = swaps[swapId];
+        require(sha256(abi.encodePacked(preimage)) == swap.preimage_hash, "preimage hash doesn't match");
+        require(!swap.complete, "swap is already completed");
+        swap.preimage = preimage;
+        swap.complete = true;
+    }
+}
@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {Owned} from "solmate/auth/Owned.sol";
+import {ERC20} from "solmate/tokens/ERC20.sol";
+import {SafeTransferLib} from "solmate/utils/SafeTransferLib.sol";
+
+/// @notice ERC-20 version of the GauloiFactory HTLC, locking tokens instead of native ETH
+contract GauloiTokenFactory is Owned(msg.sender) {
+    using SafeTransferLib for ERC20;
+
+    uint256 public swap_id = 0;
+    bool public is_active = true;
+
+    struct TokenSwap {
+        address buyer;
+        address seller;
+        address token;
+        uint256 value;
+        bytes32 preimage_hash;
+        bytes32 preimage;
+        uint256 unlock_block;
+        bool complete;
+    }
+
+    mapping(uint256 => TokenSwap) public swaps;
+    mapping(bytes32 => uint256) public hash_to_swap_map;
+
+    modifier onlyActive() {
+        require(is_active, "creating swaps isn't active");
+        _;
+    }
+
+    function set_active(bool new_active) external onlyOwner {
+        is_active = new_active;
+    }
+
+    function getSwap(uint256 swapId) external view returns (TokenSwap memory) {
+        return swaps[swapId];
+    }
+
+    /// @notice Lock `amount` of `token` for `buyer` to claim with the preimage, the tokens must be approved first
+    function createSwap(address buyer, address token, uint256 amount, bytes32 preimageHash, uint256 unlock_blocks)
+        external
+        onlyActive
+    {
+        require(amount > 0, "creating a swap without any tokens");
+        uint256 next_swap_id = ++swap_id;
+        swaps[next_swap_id] =
+            TokenSwap(buyer, msg.sender, token, amount, preimageHash, bytes32(0x0), block.number + unlock_blocks, false);
+        hash_to_swap_map[preimageHash] = next_swap_id;
+        ERC20(token).safeTransferFrom(msg.sender, address(this), amount);
+    }
+
+    function claimSwap(uint256 swapId, bytes32 preimage) external {
+        require(swapId > 0 && swapId <= swap_id, "swapId out of bounds");
+        TokenSwap storage swap = swaps[swapId];
+        require(sha256(abi.encodePacked(preimage)) == swap.preimage_hash, "preimage hash doesn't match");
+        require(!swap.complete, "swap is already completed");
+        swap.preimage = preimage;
+        swap.complete = true;
+        ERC20(swap.token).safeTransfer(swap.buyer, swap.value);
+    }
+
+    function claimTimeout(uint256 swapId) external {
+        require(swapId > 0 && swapId <= swap_id, "swapId out of bounds");
+        TokenSwap storage swap = swaps[swapId];
+        require(swap.unlock_block <= block.number, "swap not ready to claim");
+        require(!swap.complete, "swap is already completed");
+        swap.complete = true;
+        ERC20(swap.token).safeTransfer(swap.seller, swap.value);
+    }
+}
@@ -1,5 +1,4 @@
 use crate::state::GauloiState;
-use serde::Serialize;
 use ethers::utils::hex;
 use tokio::io::{stdout, AsyncWriteExt};
 use yansi::Paint;
@@ -1,15 +1,11 @@
 use std::{collections::BTreeMap, str::FromStr};
 
-use anyhow::bail;
 use bitcoin::{
-    bip32::{DerivationPath, ExtendedPubKey},
-    consensus::Decodable,
-    hashes::sha256d,
+    bip32::ExtendedPubKey,
     locktime::absolute::LockTime,
     psbt::{Input, Psbt, PsbtSighashType},
     secp256k1::Secp256k1,
-    transaction, Address, Network, OutPoint, PublicKey, ScriptBuf, Transaction, TxIn, TxOut, Txid,
-    Witness,
+    Address, OutPoint, ScriptBuf, Transaction, TxIn, TxOut, Txid, Witness,
 };
 use esplora_api::blocking::ApiClient;
 use serde::Deserialize;
@@ -23,6 +19,9 @@ pub struct RelevantTxInfo {
     pub vout: Vout,
 }
 
+/// Esplora instance the BTC side is read from and broadcast through
+const ESPLORA_URL: &str = "https://blockstream.info/api/";
+
 pub struct BitcoinApi {
     legacy_client: ApiClient,
 }
@@ -30,15 +29,25 @@ pub struct BitcoinApi {
 impl Default for BitcoinApi {
     fn default() -> Self {
         BitcoinApi {
-            legacy_client: ApiClient::new("https://blockstream.info/api/", None).unwrap(),
+            legacy_client: ApiClient::new(ESPLORA_URL, None).unwrap(),
         }
     }
 }
 
+/// An unspent output as esplora returns it, read directly for the confirmation status the client's type leaves out
+#[derive(Deserialize)]
+struct EsploraUtxo {
+    status: EsploraTxStatus,
+}
+
+#[derive(Deserialize)]
+struct EsploraTxStatus {
+    block_height: Option<u32>,
+}
+
 #[derive(Clone)]
 pub struct Vout {
     value: u64,
-    scriptpubkey: ScriptBuf,
 }
 
 impl BitcoinApi {
@@ -56,7 +65,6 @@ impl BitcoinApi {
                 spend_index: utxo.vout,
                 vout: Vout {
                     value: utxo.value as u64,
-                    scriptpubkey: address.script_pubkey(),
                 },
             });
         }
@@ -117,7 +125,6 @@ impl BitcoinApi {
 
         psbt.inputs = ins
             .iter()
-            .cloned()
             .map(|input| Input {
                 witness_utxo: Some(TxOut {
                     value: input.vout.value,
@@ -195,7 +202,6 @@ impl BitcoinApi {
 
         psbt.inputs = ins
             .iter()
-            .cloned()
             .map(|input| Input {
                 witness_utxo: Some(TxOut {
                     value: input.vout.value,
@@ -249,14 +255,25 @@ impl BitcoinApi {
 
     pub fn submit_tx(&self, transaction: Transaction) -> Result<(), Box<dyn std::error::Error>> {
         let hex = bitcoin::consensus::encode::serialize_hex(&transaction);
-        let txid = self.legacy_client.post_tx(hex.as_str()).unwrap();
+        let txid = self.legacy_client.post_tx(hex.as_str())?;
         println!("Submitted: {}", txid);
         Ok(())
     }
 
+    pub fn tip_height(&self) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(self.legacy_client.get_blocks_tip_height()? as u32)
+    }
+
+    /// Height the earliest of an address's unspent outputs confirmed at, [None] if none of them have confirmed yet
+    pub async fn first_confirmation_height(&self, address: &Address) -> Result<Option<u32>, anyhow::Error> {
+        let url = format!("{}address/{}/utxo", ESPLORA_URL, address);
+        let utxos: Vec<EsploraUtxo> = reqwest::get(url).await?.error_for_status()?.json().await?;
+        Ok(utxos.iter().filter_map(|utxo| utxo.status.block_height).min())
+    }
+
     pub fn get_balance(&self, address: &Address) -> Result<u128, Box<dyn std::error::Error>> {
         let balance: u128 = self
-            .get_utxos(&address)?
+            .get_utxos(address)?
             .iter()
             .fold(0, |amt, utxo| amt + utxo.vout.value as u128);
         Ok(balance)
@@ -0,0 +1,20 @@
+use anyhow::Result;
+use clap::Parser;
+use ethers::types::Bytes;
+
+use crate::state::GauloiState;
+
+/// Submit an ETH transaction that was exported with `--offline` and signed on the air-gapped machine
+#[derive(Parser, Debug)]
+pub struct BroadcastArgs {
+    /// Hex of the signed transaction
+    signed_tx: String,
+}
+
+pub async fn broadcast(state: &mut GauloiState, args: BroadcastArgs) -> Result<()> {
+    println!();
+    let raw: Bytes = args.signed_tx.trim().parse()?;
+    state.eth_api.broadcast_signed(&state.db, raw).await?;
+    println!("ETH transaction mined successfully");
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Withdrawing offers we've shared, and dropping offers their makers have withdrawn
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::encoding;
+use crate::envelope::{self, EnvelopeError, SignedEnvelope};
+use crate::message::{self, Message};
+use crate::offer::{OfferCancel, OfferStatus};
+use crate::qr::QrArgs;
+use crate::state::GauloiState;
+
+/// Cancel an offer we created, printing a signed cancellation for takers to `parse`
+#[derive(Parser, Debug)]
+pub struct CancelArgs {
+    /// The offer to cancel, as it was printed by `create` or `create-eth`
+    offer: String,
+
+    /// Sign the cancellation with our ETH key as well as our BTC key
+    #[clap(long)]
+    sign_eth: bool,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+}
+
+#[derive(Error, Debug)]
+#[error("The offer has been cancelled by its maker")]
+pub struct OfferCancelledError {}
+
+#[derive(Error, Debug)]
+pub enum CancelError {
+    #[error("Only offers we created can be cancelled")]
+    NotOurs,
+    #[error("A swap has already been agreed for this offer, use `refund` if anything was committed to it")]
+    AlreadyAgreed,
+}
+
+pub async fn cancel(state: &mut GauloiState, args: CancelArgs) -> Result<()> {
+    println!();
+    let (bytes, _) = envelope::open(encoding::decode_as(&args.offer, &["request"])?);
+    let us = state.our_pubkey_hash()?;
+    let (request_hash, ours) = match Message::decode(&bytes)? {
+        Message::EthRequest(request) => (message::body_hash(&bytes)?, request.buyer_pubkey_hash == us),
+        _ => {
+            let (request, request_hash) = message::decode_request(&bytes)?;
+            (request_hash, request.seller_pubkey_hash == us)
+        }
+    };
+    if !ours {
+        return Err(CancelError::NotOurs.into());
+    }
+    // fillable offers only have their fills stored as swaps, so the rest of them can still be cancelled
+    if state.db.has_offer(&request_hash)? {
+        return Err(CancelError::AlreadyAgreed.into());
+    }
+
+    state.db.set_offer_status(&request_hash, OfferStatus::Cancelled)?;
+    println!("Offer cancelled, responses to it won't be imported");
+    // an unagreed negotiation would otherwise still be open to an acceptance of our last counter-offer
+    if state.db.remove_negotiation(&request_hash)? {
+        println!("The negotiation over it is closed, further counter-offers and acceptances will be refused");
+    }
+
+    let message = Message::Cancel(OfferCancel { request_hash });
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "cancellation").await?;
+    Ok(())
+}
+
+/// Drop an offer its maker has cancelled, checking the cancellation is signed by the maker. The cancellation of an
+/// offer we haven't seen yet is kept and checked once we do
+pub fn drop_cancelled(state: &GauloiState, cancel: OfferCancel, signed: Option<SignedEnvelope>, allow_unsigned: bool) -> Result<()> {
+    let (pubkey_hash, eth_address) = if let Some(request) = state.db.get_pending_offer(&cancel.request_hash)? {
+        (request.seller_pubkey_hash, request.seller_eth_address)
+    } else if let Some(request) = state.db.get_pending_eth_offer(&cancel.request_hash)? {
+        (request.buyer_pubkey_hash, request.buyer_eth_address)
+    } else {
+        return keep_unmatched(state, cancel, signed, allow_unsigned);
+    };
+    envelope::check_sender(state, signed.as_ref(), pubkey_hash, eth_address, allow_unsigned)?;
+
+    state.db.set_offer_status(&cancel.request_hash, OfferStatus::Cancelled)?;
+    println!("Offer dropped, its maker cancelled it");
+    if state.db.has_offer(&cancel.request_hash)? {
+        println!(
+            "{} we'd already responded to it, the maker won't import the response so don't commit anything to the swap",
+            Paint::yellow("Warning:")
+        );
+    }
+    Ok(())
+}
+
+/// Keep the cancellation of an offer we haven't parsed, there's no maker to check the signature against until we do
+fn keep_unmatched(state: &GauloiState, cancel: OfferCancel, signed: Option<SignedEnvelope>, allow_unsigned: bool) -> Result<()> {
+    match signed {
+        Some(envelope) => {
+            state.db.put_unmatched_cancel(&cancel.request_hash, &envelope.to_cbor()?)?;
+            println!("Offer not seen yet, it'll be refused when parsed if the cancellation is signed by its maker");
+        }
+        None if allow_unsigned => {
+            println!("{} the message isn't signed, its sender can't be checked", Paint::yellow("Warning:"));
+            state.db.set_offer_status(&cancel.request_hash, OfferStatus::Cancelled)?;
+            println!("Offer not seen yet, it'll be refused when parsed");
+        }
+        None => bail!(EnvelopeError::Unsigned),
+    }
+    Ok(())
+}
+
+/// Refuse an offer whose cancellation arrived before it, once the cancellation checks out against the offer's maker
+pub fn check_unmatched(state: &GauloiState, request_hash: &[u8; 32], pubkey_hash: [u8; 20], eth_address: [u8; 20]) -> Result<()> {
+    let Some(bytes) = state.db.take_unmatched_cancel(request_hash)? else {
+        return Ok(());
+    };
+    match envelope::open(bytes).1.map(|envelope| envelope.verify(state, pubkey_hash, eth_address)) {
+        Some(Ok(_)) => {
+            state.db.set_offer_status(request_hash, OfferStatus::Cancelled)?;
+            Err(OfferCancelledError {}.into())
+        }
+        _ => {
+            println!("{} ignoring a cancellation of this offer that isn't signed by its maker", Paint::yellow("Warning:"));
+            Ok(())
+        }
+    }
+}
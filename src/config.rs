@@ -0,0 +1,103 @@
+//! Local configuration for the chains this CLI talks to, kept next to the hot wallet
+
+use std::path::Path;
+
+use anyhow::Result;
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::eth_api::EthApi;
+
+/// Config lives in the folder the CLI is executed in, like the hot wallet and swap storage
+pub const CONFIG_PATH: &str = "gauloi.toml";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GauloiConfig {
+    /// The EVM chain the ETH leg of swaps happens on
+    #[serde(default)]
+    pub eth: EthChainConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EthChainConfig {
+    /// JSON-RPC endpoint for the chain, transactions are sent through this one
+    pub rpc_url: String,
+    /// More JSON-RPC endpoints for the same chain, critical reads need a quorum of all the endpoints to agree
+    #[serde(default)]
+    pub extra_rpc_urls: Vec<String>,
+    /// How many endpoints have to agree on a critical read, defaults to a majority
+    #[serde(default)]
+    pub quorum: Option<usize>,
+    /// Address of the GauloiFactory contract on the chain
+    pub gauloi_address: Address,
+    /// Address of the GauloiTokenFactory contract used for ERC-20 swaps, if deployed on the chain
+    #[serde(default)]
+    pub token_gauloi_address: Option<Address>,
+    /// The GauloiRelayFactory used for swaps the BTC seller claims through a relayer, if deployed on this chain
+    #[serde(default)]
+    pub relay_gauloi_address: Option<Address>,
+    /// Encrypted JSON keystore holding the ETH account to use instead of the hot wallet derived one
+    #[serde(default)]
+    pub keystore: Option<String>,
+    /// Air-gapped account that signs swap transactions exported with `--offline`, defaults to our ETH account
+    #[serde(default)]
+    pub offline_signer: Option<Address>,
+    /// Flashbots-style endpoint to send commits and claims through instead of the public mempool
+    #[serde(default)]
+    pub private_rpc_url: Option<String>,
+    /// Seconds to wait for a private transaction to be mined before sending it to the public mempool
+    #[serde(default = "default_private_fallback_secs")]
+    pub private_fallback_secs: u64,
+    /// Chainlink-style aggregator pricing 1 BTC in ETH (BTC / ETH), offer rates are checked against it if set
+    #[serde(default)]
+    pub btc_price_feed: Option<Address>,
+    /// How far in percent an offer's rate can be from the price feed before it needs `--ignore-price`
+    #[serde(default = "default_max_price_deviation")]
+    pub max_price_deviation: f64,
+}
+
+fn default_private_fallback_secs() -> u64 {
+    120
+}
+
+fn default_max_price_deviation() -> f64 {
+    5.0
+}
+
+impl Default for EthChainConfig {
+    fn default() -> Self {
+        EthChainConfig {
+            rpc_url: "https://eth.llamarpc.com".to_string(),
+            extra_rpc_urls: Vec::new(),
+            quorum: None,
+            gauloi_address: EthApi::mainnet_gauloi(),
+            token_gauloi_address: None,
+            relay_gauloi_address: None,
+            keystore: None,
+            offline_signer: None,
+            private_rpc_url: None,
+            private_fallback_secs: default_private_fallback_secs(),
+            btc_price_feed: None,
+            max_price_deviation: default_max_price_deviation(),
+        }
+    }
+}
+
+impl GauloiConfig {
+    /// Load the config from [CONFIG_PATH], falling back to the mainnet defaults if there isn't one yet
+    pub fn load() -> Result<Self> {
+        let path = Path::new(CONFIG_PATH);
+        if !path.exists() {
+            return Ok(GauloiConfig::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Write the config back to [CONFIG_PATH]
+    pub fn save(&self) -> Result<()> {
+        let contents = toml::to_string_pretty(self)?;
+        std::fs::write(CONFIG_PATH, contents)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::bail;
+use clap::{Parser, Subcommand};
+use ethers::types::Address;
+use ethers::utils::hex;
+use yansi::Paint;
+
+use crate::eth_api::Factory;
+use crate::state::GauloiState;
+
+/// Manage the GauloiFactory contract used for the ETH side of swaps
+#[derive(Parser, Debug)]
+pub struct ContractArgs {
+    #[clap(subcommand)]
+    command: ContractCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum ContractCommand {
+    /// Deploy a new GauloiFactory with the hot wallet and save its address in the chain config
+    Deploy {
+        /// Deploy the GauloiTokenFactory for ERC-20 swaps instead, from its bundled artifact
+        #[clap(long, conflicts_with = "relay")]
+        token: bool,
+        /// Deploy the GauloiRelayFactory for relayed claims instead, from its bundled artifact
+        #[clap(long)]
+        relay: bool,
+    },
+    /// Show the owner and active state of the configured GauloiFactory
+    Status,
+    /// Stop new swaps being created on the GauloiFactory (owner only)
+    Pause,
+    /// Allow new swaps to be created on the GauloiFactory again (owner only)
+    Resume,
+    /// Transfer ownership of the GauloiFactory to another address (owner only)
+    TransferOwner {
+        /// The new owner's ETH address
+        new_owner: Address,
+    },
+}
+
+pub async fn contract(state: &mut GauloiState, args: ContractArgs) -> Result<(), anyhow::Error> {
+    println!();
+    match args.command {
+        ContractCommand::Deploy { token: true, .. } => deploy_factory(state, Factory::Token).await,
+        ContractCommand::Deploy { relay: true, .. } => deploy_factory(state, Factory::Relay).await,
+        ContractCommand::Deploy { .. } => deploy(state).await,
+        ContractCommand::Status => status(state).await,
+        ContractCommand::Pause => set_active(state, false).await,
+        ContractCommand::Resume => set_active(state, true).await,
+        ContractCommand::TransferOwner { new_owner } => transfer_owner(state, new_owner).await,
+    }
+}
+
+async fn deploy(state: &mut GauloiState) -> Result<(), anyhow::Error> {
+    let signer = state.get_wallet()?;
+    println!("Deploying GauloiFactory...");
+    let address = state.eth_api.deploy_gauloi(signer).await?;
+    println!("GauloiFactory deployed at: {}", Paint::green(hex::encode_prefixed(address)));
+
+    state.eth_api.set_gauloi_address(address);
+    state.config.eth.gauloi_address = address;
+    state.config.save()?;
+    println!("Saved contract address to the chain config");
+    Ok(())
+}
+
+async fn deploy_factory(state: &mut GauloiState, factory: Factory) -> Result<(), anyhow::Error> {
+    let signer = state.get_wallet()?;
+    println!("Deploying {}...", factory.name());
+    let address = state.eth_api.deploy_factory(signer, factory).await?;
+    println!("{} deployed at: {}", factory.name(), Paint::green(hex::encode_prefixed(address)));
+
+    state.eth_api.set_factory_address(factory, address);
+    match factory {
+        Factory::Gauloi => state.config.eth.gauloi_address = address,
+        Factory::Relay => state.config.eth.relay_gauloi_address = Some(address),
+        Factory::Token => state.config.eth.token_gauloi_address = Some(address),
+    }
+    state.config.save()?;
+    println!("Saved contract address to the chain config");
+    Ok(())
+}
+
+async fn status(state: &mut GauloiState) -> Result<(), anyhow::Error> {
+    let owner = state.eth_api.owner().await?;
+    let active = state.eth_api.is_active().await?;
+    let swap_count = state.eth_api.swap_count().await?;
+    let we_own = owner == state.eth_address()?;
+
+    println!("{}", Paint::yellow("=== GauloiFactory ==="));
+    println!("Address: {}", hex::encode_prefixed(state.eth_api.gauloi_address()));
+    println!("Owner: {}{}", hex::encode_prefixed(owner), if we_own { " (us)" } else { "" });
+    if active {
+        println!("Swap creation: {}", Paint::green("active"));
+    } else {
+        println!("Swap creation: {}", Paint::red("paused"));
+    }
+    println!("Swaps created: {}", swap_count);
+    Ok(())
+}
+
+/// Bail early if we aren't the owner, rather than sending a transaction that will revert
+async fn ensure_owner(state: &GauloiState) -> Result<(), anyhow::Error> {
+    let owner = state.eth_api.owner().await?;
+    if owner != state.eth_address()? {
+        bail!("Only the owner ({}) can administer this contract", hex::encode_prefixed(owner));
+    }
+    Ok(())
+}
+
+/// Ask the user to confirm an owner action, defaulting to no
+fn confirm(state: &mut GauloiState, prompt: &str) -> Result<bool, anyhow::Error> {
+    let input = state.editor.readline(format!("{} y/[N]? ", prompt).as_str())?;
+    Ok(input.trim().to_lowercase().starts_with('y'))
+}
+
+async fn set_active(state: &mut GauloiState, active: bool) -> Result<(), anyhow::Error> {
+    ensure_owner(state).await?;
+    let action = if active { "Resume" } else { "Pause" };
+    if !confirm(state, format!("{} swap creation on {}", action, hex::encode_prefixed(state.eth_api.gauloi_address())).as_str())? {
+        println!("{}", Paint::red("Not changing contract state, exiting"));
+        return Ok(());
+    }
+
+    let signer = state.get_wallet()?;
+    println!("Sending set_active({})...", active);
+    state.eth_api.set_active(signer, active).await?;
+    if active {
+        println!("Swap creation {}", Paint::green("resumed"));
+    } else {
+        println!("Swap creation {}", Paint::red("paused"));
+    }
+    Ok(())
+}
+
+async fn transfer_owner(state: &mut GauloiState, new_owner: Address) -> Result<(), anyhow::Error> {
+    ensure_owner(state).await?;
+    println!(
+        "{}",
+        Paint::red("Transferring ownership can't be undone by us, double check the new owner address!")
+    );
+    if !confirm(state, format!("Transfer ownership to {}", hex::encode_prefixed(new_owner)).as_str())? {
+        println!("{}", Paint::red("Not transferring ownership, exiting"));
+        return Ok(());
+    }
+
+    let signer = state.get_wallet()?;
+    println!("Sending transferOwnership...");
+    state.eth_api.transfer_ownership(signer, new_owner).await?;
+    println!("Ownership transferred to {}", Paint::green(hex::encode_prefixed(new_owner)));
+    Ok(())
+}
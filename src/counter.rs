@@ -0,0 +1,338 @@
+//! Counter-offers, so the two sides of a swap can negotiate its terms before agreeing to them
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use bitcoin::Amount;
+use clap::Parser;
+use ethers::types::U256;
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::cancel::{self, OfferCancelledError};
+use crate::encoding;
+use crate::envelope::{self, SignedEnvelope};
+use crate::expiry;
+use crate::message::{self, Message};
+use crate::offer::{format_bought, CounterAccept, Negotiation, OfferCounter, OfferRequest, OfferStatus};
+use crate::price;
+use crate::qr::QrArgs;
+use crate::state::GauloiState;
+use crate::timelock::{self, Lockup};
+
+/// Counter an offer or counter-offer with new terms, accept a counter-offer, or take in the other side's acceptance
+#[derive(Parser, Debug)]
+pub struct CounterArgs {
+    /// The offer, counter-offer or acceptance
+    message: String,
+
+    /// Accept the counter-offer's terms instead of proposing new ones
+    #[clap(long)]
+    accept: bool,
+
+    /// Amount of BTC sold in the new terms
+    #[clap(short, long)]
+    sell: Option<f64>,
+
+    /// Amount of ETH (or tokens) bought in the new terms
+    #[clap(short, long)]
+    buy: Option<f64>,
+
+    /// How long the BTC is locked in the Bitcoin HTLC in the new terms, as a time like 24h or a number of BTC blocks
+    #[clap(long)]
+    lockup_btc: Option<Lockup>,
+
+    /// How long the ETH is locked in the swap contract in the new terms, as a time like 12h or a number of Eth blocks
+    #[clap(long)]
+    lockup_eth: Option<Lockup>,
+
+    /// Go ahead even if the rate is further off the price feed than allowed
+    #[clap(long)]
+    ignore_price: bool,
+
+    /// Take a message that isn't signed by the other side
+    #[clap(long)]
+    allow_unsigned: bool,
+
+    /// Sign our message with our ETH key as well as our BTC key
+    #[clap(long)]
+    sign_eth: bool,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+}
+
+impl CounterArgs {
+    fn has_terms(&self) -> bool {
+        self.sell.is_some() || self.buy.is_some() || self.lockup_btc.is_some() || self.lockup_eth.is_some()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CounterError {
+    #[error("Offers made by the ETH side can't be countered, make a new offer instead")]
+    EthOffer,
+    #[error("Fillable offers can't be countered, fill them with `parse` instead")]
+    Fillable,
+    #[error("This is an offer as it was made, accept it with `parse`")]
+    AcceptRequest,
+    #[error("We're already negotiating this offer, counter the latest counter-offer instead")]
+    AlreadyNegotiating,
+    #[error("Round {got} is out of order, the negotiation is at round {at}")]
+    OutOfOrder { got: u32, at: u32 },
+    #[error("The message isn't from the other side of this negotiation")]
+    NotCounterparty,
+    #[error("The negotiation has already been agreed at round {0}")]
+    AlreadyAgreed(u32),
+    #[error("Only the latest round of the negotiation can be accepted")]
+    NotLatest,
+    #[error("An offer {0} can't be used to negotiate")]
+    Unexpected(&'static str),
+}
+
+pub async fn counter(state: &mut GauloiState, args: CounterArgs) -> Result<()> {
+    println!();
+    let (bytes, _) = encoding::decode(&args.message)?;
+    let (bytes, signed) = envelope::open(bytes);
+    match Message::decode(&bytes)? {
+        Message::Request(_) => {
+            let (request, request_hash) = message::decode_request(&bytes)?;
+            counter_request(state, args, request, request_hash, signed).await
+        }
+        Message::Counter(counter) => receive_counter(state, args, counter, signed).await,
+        Message::Accept(accept) => receive_accept(state, accept, signed),
+        Message::EthRequest(_) => Err(CounterError::EthOffer.into()),
+        other => Err(CounterError::Unexpected(other.kind()).into()),
+    }
+}
+
+/// Start negotiating an offer as its taker
+async fn counter_request(
+    state: &mut GauloiState,
+    args: CounterArgs,
+    request: OfferRequest,
+    request_hash: [u8; 32],
+    signed: Option<SignedEnvelope>,
+) -> Result<()> {
+    if args.accept {
+        return Err(CounterError::AcceptRequest.into());
+    }
+    if request.min_fill.is_some() {
+        return Err(CounterError::Fillable.into());
+    }
+    if request.seller_pubkey_hash == state.our_pubkey_hash()? {
+        println!("This is an offer you created!");
+        return Ok(());
+    }
+    envelope::check_sender(state, signed.as_ref(), request.seller_pubkey_hash, request.seller_eth_address, args.allow_unsigned)?;
+    cancel::check_unmatched(state, &request_hash, request.seller_pubkey_hash, request.seller_eth_address)?;
+    expiry::check(&state.btc_api, request.expiry)?;
+    if state.db.get_offer_status(&request_hash)? == Some(OfferStatus::Cancelled) {
+        return Err(OfferCancelledError {}.into());
+    }
+    if state.db.get_negotiation(&request_hash)?.is_some() {
+        return Err(CounterError::AlreadyNegotiating.into());
+    }
+
+    state.db.add_pending_offer(request)?;
+    let negotiation = Negotiation { request, counters: Vec::new(), agreed: None };
+    // the request has no ETH lockup, so start from the one parse suggests
+    let terms = (request.sold, request.bought, request.lockup_btc, timelock::default_eth_for(request.lockup_btc));
+    propose(state, &args, negotiation, request_hash, terms).await
+}
+
+/// Take in the other side's counter-offer, then accept it or counter it again
+async fn receive_counter(
+    state: &mut GauloiState,
+    args: CounterArgs,
+    counter: OfferCounter,
+    signed: Option<SignedEnvelope>,
+) -> Result<()> {
+    envelope::check_sender(state, signed.as_ref(), counter.sender_pubkey_hash, counter.sender_eth_address, args.allow_unsigned)?;
+    let us = state.our_pubkey_hash()?;
+
+    let mut negotiation = match state.db.get_negotiation(&counter.request_hash)? {
+        Some(negotiation) => negotiation,
+        None => match state.db.get_pending_offer(&counter.request_hash)? {
+            // the taker's first counter to an offer we made
+            Some(request) if request.seller_pubkey_hash == us => Negotiation { request, counters: Vec::new(), agreed: None },
+            _ => bail!("No offer / request locally"),
+        },
+    };
+    if let Some(round) = negotiation.agreed {
+        return Err(CounterError::AlreadyAgreed(round).into());
+    }
+    let at = negotiation.counters.len() as u32 + 1;
+    if counter.round != at {
+        return Err(CounterError::OutOfOrder { got: counter.round, at }.into());
+    }
+
+    // the taker sends the odd rounds to the maker and the maker the even ones back
+    let maker = negotiation.request.seller_pubkey_hash;
+    let from_counterparty = match negotiation.taker() {
+        None => maker == us && counter.sender_pubkey_hash != us,
+        Some(taker) if counter.round % 2 == 1 => maker == us && counter.sender_pubkey_hash == taker.sender_pubkey_hash,
+        Some(taker) => taker.sender_pubkey_hash == us && counter.sender_pubkey_hash == maker,
+    };
+    if !from_counterparty {
+        return Err(CounterError::NotCounterparty.into());
+    }
+    expiry::check(&state.btc_api, negotiation.request.expiry)?;
+    if state.db.get_offer_status(&counter.request_hash)? == Some(OfferStatus::Cancelled) {
+        return Err(OfferCancelledError {}.into());
+    }
+
+    negotiation.counters.push(counter);
+    state.db.put_negotiation(&counter.request_hash, &negotiation)?;
+    print_terms(state, &negotiation.request, &counter).await?;
+
+    let accept = if args.accept || args.has_terms() {
+        args.accept
+    } else {
+        let input = state.editor.readline("Accept these terms? [Y]/n")?;
+        input.is_empty() || !input.to_lowercase().contains("n")
+    };
+    if accept {
+        accept_counter(state, &args, &negotiation.request, &counter).await
+    } else {
+        let terms = (counter.sold, counter.bought, counter.lockup_btc, counter.lockup_eth);
+        propose(state, &args, negotiation, counter.request_hash, terms).await
+    }
+}
+
+/// Propose the next round of terms, starting from the last ones for anything not given in `args`
+async fn propose(
+    state: &mut GauloiState,
+    args: &CounterArgs,
+    mut negotiation: Negotiation,
+    request_hash: [u8; 32],
+    (sold, bought, lockup_btc, lockup_eth): (u128, u128, u16, u32),
+) -> Result<()> {
+    let decimals = negotiation.request.token_decimals;
+    let sold_btc = Amount::from_sat(sold as u64).to_btc();
+    let sold_btc = prompt_or(state, args.sell, "Enter amount of BTC sold", sold_btc)?;
+    let bought_units: f64 = format_bought(bought, decimals).parse()?;
+    let bought_units = prompt_or(state, args.buy, "Enter amount bought", bought_units)?;
+    let lockup_btc = prompt_or(state, args.lockup_btc, "Enter BTC lockup time, e.g. 24h, or in Bitcoin blocks", Lockup::Blocks(lockup_btc as u32))?;
+    let lockup_btc = lockup_btc.btc_blocks()?;
+    let lockup_eth = prompt_or(state, args.lockup_eth, "Enter ETH lockup time, e.g. 12h, or in Eth blocks", Lockup::Blocks(lockup_eth))?;
+    let lockup_eth = lockup_eth.eth_blocks();
+    timelock::check(lockup_btc, lockup_eth)?;
+
+    let sold = Amount::from_btc(sold_btc)?.to_sat() as u128;
+    let bought = U256::from(ethers::utils::parse_units(bought_units, decimals as u32)?).as_u128();
+    price::check_offer_rate(state, sold, bought, decimals, negotiation.request.token_address.map(Into::into), args.ignore_price).await?;
+
+    let counter = OfferCounter {
+        request_hash,
+        round: negotiation.counters.len() as u32 + 1,
+        sold,
+        bought,
+        lockup_btc,
+        lockup_eth,
+        sender_pubkey_hash: state.our_pubkey_hash()?,
+        sender_eth_address: state.eth_address()?.to_fixed_bytes(),
+    };
+    negotiation.counters.push(counter);
+    state.db.put_negotiation(&request_hash, &negotiation)?;
+
+    let message = Message::Counter(counter);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, &format!("counter-offer (round {})", counter.round)).await?;
+    Ok(())
+}
+
+/// Agree to the other side's counter-offer, making it the offer for the swap
+async fn accept_counter(state: &mut GauloiState, args: &CounterArgs, request: &OfferRequest, counter: &OfferCounter) -> Result<()> {
+    timelock::check(counter.lockup_btc, counter.lockup_eth)?;
+    let token = request.token_address.map(Into::into);
+    price::check_offer_rate(state, counter.sold, counter.bought, request.token_decimals, token, args.ignore_price).await?;
+
+    let offer = state.db.agree_negotiation(&counter.request_hash, counter.round)?;
+    let accept = CounterAccept {
+        request_hash: counter.request_hash,
+        round: counter.round,
+        sender_pubkey_hash: state.our_pubkey_hash()?,
+        sender_eth_address: state.eth_address()?.to_fixed_bytes(),
+    };
+
+    let message = Message::Accept(accept);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "acceptance").await?;
+    println!("Swap index: {}", state.db.get_swap_index(&offer)?.ok_or(anyhow!("Agreed offer wasn't stored"))?);
+    Ok(())
+}
+
+/// Take in the other side's agreement to our latest counter-offer
+fn receive_accept(state: &mut GauloiState, accept: CounterAccept, signed: Option<SignedEnvelope>) -> Result<()> {
+    // acceptances can't be taken unsigned, they're what commits us to the terms
+    envelope::check_sender(state, signed.as_ref(), accept.sender_pubkey_hash, accept.sender_eth_address, false)?;
+    let us = state.our_pubkey_hash()?;
+
+    let Some(negotiation) = state.db.get_negotiation(&accept.request_hash)? else {
+        bail!("No negotiation for this offer locally")
+    };
+    if let Some(round) = negotiation.agreed {
+        return Err(CounterError::AlreadyAgreed(round).into());
+    }
+    let (Some(taker), Some(latest)) = (negotiation.taker(), negotiation.latest()) else {
+        bail!("No negotiation for this offer locally")
+    };
+    if accept.round != latest.round {
+        return Err(CounterError::NotLatest.into());
+    }
+    let proposer = if latest.round % 2 == 1 { taker.sender_pubkey_hash } else { negotiation.request.seller_pubkey_hash };
+    let accepter = if latest.round % 2 == 1 { negotiation.request.seller_pubkey_hash } else { taker.sender_pubkey_hash };
+    if proposer != us || accept.sender_pubkey_hash != accepter {
+        return Err(CounterError::NotCounterparty.into());
+    }
+    expiry::check(&state.btc_api, negotiation.request.expiry)?;
+    if state.db.get_offer_status(&accept.request_hash)? == Some(OfferStatus::Cancelled) {
+        return Err(OfferCancelledError {}.into());
+    }
+
+    let offer = state.db.agree_negotiation(&accept.request_hash, accept.round)?;
+    println!("Counter-offer accepted!");
+    println!("Swap index: {}", state.db.get_swap_index(&offer)?.ok_or(anyhow!("Agreed offer wasn't stored"))?);
+    Ok(())
+}
+
+async fn print_terms(state: &GauloiState, request: &OfferRequest, counter: &OfferCounter) -> Result<()> {
+    let asset = match request.token_address {
+        Some(token) => state.eth_api.checked_token_symbol(token.into(), request.token_decimals).await?,
+        None => "ETH".to_string(),
+    };
+    println!("{}", Paint::yellow(format!("=== Counter-offer, round {} ===", counter.round)));
+    println!(
+        "Sell {} for {}{} (offered: {} for {}{})",
+        Amount::from_sat(counter.sold as u64),
+        format_bought(counter.bought, request.token_decimals),
+        asset,
+        Amount::from_sat(request.sold as u64),
+        format_bought(request.bought, request.token_decimals),
+        asset,
+    );
+    println!(
+        "Lockups: {} Bitcoin blocks (about {}), {} Eth blocks (about {})",
+        counter.lockup_btc,
+        timelock::format_duration(timelock::btc_duration(counter.lockup_btc)),
+        counter.lockup_eth,
+        timelock::format_duration(timelock::eth_duration(counter.lockup_eth)),
+    );
+    Ok(())
+}
+
+/// The value given on the command line, or else what's entered at a prompt with `default` for an empty entry
+fn prompt_or<T: FromStr + Display>(state: &mut GauloiState, given: Option<T>, prompt: &str, default: T) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    if let Some(given) = given {
+        return Ok(given);
+    }
+    let entered = state.editor.readline(format!("{} [default: {}]: ", prompt, default).as_str())?;
+    if entered.is_empty() {
+        Ok(default)
+    } else {
+        Ok(entered.parse()?)
+    }
+}
@@ -1,43 +1,92 @@
-use std::error::Error;
-use std::str::FromStr;
-
-use bitcoin::{Address, Amount};
+use bitcoin::Amount;
 use bitcoin::hashes::{Hash, sha256};
-use ciborium::into_writer;
 use clap::Parser;
-use ethers::utils::hex;
-use rand::prelude::RngCore;
+use ethers::types::{H160, U256};
 use thiserror::Error;
 use yansi::Paint;
 
-use crate::offer::{OfferRequest, self};
+use crate::envelope::SignedEnvelope;
+use crate::message::{self, Message};
+use crate::expiry;
+use crate::offer::{FillState, OfferRequest, self};
+use crate::price;
+use crate::qr::QrArgs;
 use crate::state::GauloiState;
+use crate::timelock::{self, Lockup};
 
 /// Create the offer for a swap originating from Bitcoin
 ///
 #[derive(Parser, Debug)]
 pub struct CreateOfferArgs {
-    /// Number of blocks for the BTC to be locked in the Bitcoin HTLC
+    /// How long the BTC is locked in the Bitcoin HTLC, as a time like 24h or a number of BTC blocks
     #[clap(short, long)]
-    lockup_time: Option<u8>,
+    lockup_time: Option<Lockup>,
 
     /// Amount of BTC to offer
     #[clap(short, long)]
     sell: Option<f64>,
 
-    /// Amount of ETH to receive
+    /// Amount of ETH (or tokens, if --token is set) to receive
     #[clap(short, long)]
     buy: Option<f64>,
+
+    /// Address of an ERC-20 token to receive instead of ETH
+    #[clap(short, long)]
+    token: Option<H160>,
+
+    /// Lock the ETH in the GauloiRelayFactory so we can claim it through a relayer without holding gas
+    #[clap(long)]
+    relayed: bool,
+
+    /// Create the offer even if its rate is further off the price feed than allowed
+    #[clap(long)]
+    ignore_price: bool,
+
+    /// Sign the offer with our ETH key as well as our BTC key
+    #[clap(long)]
+    sign_eth: bool,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+
+    /// Minutes until the offer expires
+    #[clap(long, default_value = "60")]
+    expires_in: u64,
+
+    /// BTC block height the offer expires at, instead of a time
+    #[clap(long, conflicts_with = "expires-in")]
+    expires_at_height: Option<u32>,
+
+    /// Let several takers each fill part of the offer, with at least this much BTC
+    #[clap(long)]
+    min_fill: Option<f64>,
 }
 
 #[derive(Error, Debug)]
 #[error("You don't have enough unspent outputs to create this swap")]
 pub struct InsufficientBtcBalanceError {}
 
+#[derive(Error, Debug)]
+#[error("Relayed claims are only supported when buying native ETH")]
+pub struct RelayedTokenSwapError {}
+
+#[derive(Error, Debug)]
+#[error("The minimum fill can't be more than the BTC sold")]
+pub struct MinFillTooLargeError {}
+
 pub async fn create_offer(state: &mut GauloiState, args: CreateOfferArgs) -> Result<(), Box<dyn std::error::Error>> {
     println!();
-    let p2wpkh = state.p2wpkh_address()?;
-    let our_btc_balance = state.btc_api.get_balance(&p2wpkh);
+    if args.relayed {
+        if args.token.is_some() {
+            return Err(RelayedTokenSwapError {}.into());
+        }
+        state.eth_api.relay_gauloi_address()?;
+    }
+    let (asset, decimals) = match args.token {
+        Some(token) => state.eth_api.token_info(token).await?,
+        None => ("ETH".to_string(), offer::ETH_DECIMALS),
+    };
+    let our_btc_balance = state.our_btc_balance()?;
     let editor = &mut state.editor;
     let sold = if let Some(amt) = args.sell {
         amt
@@ -46,7 +95,7 @@ pub async fn create_offer(state: &mut GauloiState, args: CreateOfferArgs) -> Res
         entered.parse()?
     };
 
-    if let Ok(balance) = our_btc_balance {
+    if let Some(balance) = our_btc_balance {
         let sold_amt = Amount::from_btc(sold)?;
         let balance_amt = Amount::from_sat(balance as u64);
         if sold_amt > balance_amt {
@@ -60,30 +109,48 @@ pub async fn create_offer(state: &mut GauloiState, args: CreateOfferArgs) -> Res
     let bought = if let Some(amt) = args.buy {
         amt
     } else {
-        let entered = editor.readline("Enter amount of ETH to buy: ")?;
+        let entered = editor.readline(format!("Enter amount of {} to buy: ", asset).as_str())?;
         entered.parse()?
     };
     let lockup = if let Some(time) = args.lockup_time {
         time
     } else {
-        let entered = editor.readline("Enter lockup time in Bitcoin blocks [default: 10]: ")?;
+        let entered = editor.readline(format!("Enter lockup time, e.g. 24h, or in Bitcoin blocks [default: {}]: ", timelock::DEFAULT_BTC_LOCKUP).as_str())?;
         if entered.is_empty() {
-            10
+            timelock::DEFAULT_BTC_LOCKUP
         } else {
             entered.parse()?
         }
     };
+    let lockup = lockup.btc_blocks()?;
+    timelock::check_btc(lockup)?;
     println!();
-    println!("Selling {}BTC for {}ETH", sold, bought);
-    println!("Blocks your BTC will be locked up in HTLC for: {}", lockup);
+    println!("Selling {}BTC for {}{}", sold, bought, asset);
+    println!("Blocks your BTC will be locked up in HTLC for: {} (about {})", lockup, timelock::format_duration(timelock::btc_duration(lockup)));
 
     println!();
 
     let sold_sats: u128 = bitcoin::Amount::from_btc(sold)?.to_sat() as u128;
-    let bought_wei: u128 = ethers::utils::parse_ether(bought)?.as_u128();
-    let preimage = GauloiState::preimage();
+    let bought_wei: u128 = U256::from(ethers::utils::parse_units(bought, decimals as u32)?).as_u128();
+    price::check_offer_rate(state, sold_sats, bought_wei, decimals, args.token, args.ignore_price).await?;
+
+    let expiry = expiry::for_new_offer(args.expires_in, args.expires_at_height)?;
+    println!("Offer expires at {}", expiry);
 
-    let hasher = sha256::Hash::hash(&preimage);
+    let min_fill = match args.min_fill {
+        Some(min_fill) => Some(Amount::from_btc(min_fill)?.to_sat() as u128),
+        None => None,
+    };
+    if min_fill.is_some_and(|min_fill| min_fill > sold_sats) {
+        return Err(MinFillTooLargeError {}.into());
+    }
+
+    // fillable offers get a preimage per fill when it's confirmed instead
+    let preimage = GauloiState::preimage();
+    let preimage_hash = match min_fill {
+        Some(_) => [0u8; 32],
+        None => sha256::Hash::hash(&preimage).to_byte_array(),
+    };
 
     let offer = OfferRequest {
         version: offer::VERSION,
@@ -92,15 +159,22 @@ pub async fn create_offer(state: &mut GauloiState, args: CreateOfferArgs) -> Res
         lockup_btc: lockup,
         seller_pubkey_hash: state.btc_address()?.pubkey_hash().to_byte_array(),
         seller_eth_address: state.eth_address()?.to_fixed_bytes(),
-        preimage_hash: hasher.to_byte_array(),
+        preimage_hash,
+        token_address: args.token.map(|token| token.to_fixed_bytes()),
+        token_decimals: decimals,
+        relayed: args.relayed,
+        expiry: Some(expiry),
+        min_fill,
     };
 
     // Add the pending offer nad pre-image to the local storage (full preimage separate)
     state.db.add_pending_offer(offer)?;
-    state.db.add_preimage(offer, preimage)?;
+    match min_fill {
+        Some(_) => state.db.put_fill_state(&message::request_hash(&offer)?, &FillState { remaining: sold_sats, fills: Vec::new() })?,
+        None => state.db.add_preimage(offer, preimage)?,
+    }
 
-    let mut bytes = Vec::new();
-    into_writer(&offer, &mut bytes)?;
-    println!("hex for offer:\n{}", hex::encode(bytes));
+    let message = Message::Request(offer);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "offer").await?;
     Ok(())
 }
\ No newline at end of file
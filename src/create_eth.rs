@@ -0,0 +1,139 @@
+use bitcoin::Amount;
+use clap::Parser;
+use ethers::types::{H160, U256};
+use yansi::Paint;
+
+use crate::create::RelayedTokenSwapError;
+use crate::envelope::SignedEnvelope;
+use crate::expiry;
+use crate::message::{self, Message};
+use crate::offer::{self, format_bought, EthOfferRequest};
+use crate::price;
+use crate::qr::QrArgs;
+use crate::state::GauloiState;
+use crate::timelock::{self, Lockup};
+
+/// Create the offer for a swap originating from Ethereum, the BTC side makes the preimage when it accepts
+#[derive(Parser, Debug)]
+pub struct CreateEthOfferArgs {
+    /// How long the ETH is locked in the swap contract, as a time like 12h or a number of Eth blocks
+    #[clap(short, long)]
+    lockup_time: Option<Lockup>,
+
+    /// Amount of ETH (or tokens, if --token is set) to offer
+    #[clap(short, long)]
+    sell: Option<f64>,
+
+    /// Amount of BTC to receive
+    #[clap(short, long)]
+    buy: Option<f64>,
+
+    /// Address of an ERC-20 token to sell instead of ETH
+    #[clap(short, long)]
+    token: Option<H160>,
+
+    /// Lock the ETH in the GauloiRelayFactory so the BTC side can claim it through a relayer without holding gas
+    #[clap(long)]
+    relayed: bool,
+
+    /// Create the offer even if its rate is further off the price feed than allowed
+    #[clap(long)]
+    ignore_price: bool,
+
+    /// Sign the offer with our ETH key as well as our BTC key
+    #[clap(long)]
+    sign_eth: bool,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+
+    /// Minutes until the offer expires
+    #[clap(long, default_value = "60")]
+    expires_in: u64,
+
+    /// BTC block height the offer expires at, instead of a time
+    #[clap(long, conflicts_with = "expires-in")]
+    expires_at_height: Option<u32>,
+}
+
+pub async fn create_eth_offer(state: &mut GauloiState, args: CreateEthOfferArgs) -> Result<(), Box<dyn std::error::Error>> {
+    println!();
+    if args.relayed {
+        if args.token.is_some() {
+            return Err(RelayedTokenSwapError {}.into());
+        }
+        state.eth_api.relay_gauloi_address()?;
+    }
+    let eth_address = state.eth_address()?;
+    let (asset, decimals) = match args.token {
+        Some(token) => state.eth_api.token_info(token).await?,
+        None => ("ETH".to_string(), offer::ETH_DECIMALS),
+    };
+    let our_balance = state.eth_api.get_balance_of(&eth_address, args.token).await?;
+
+    let editor = &mut state.editor;
+    let sold = if let Some(amt) = args.sell {
+        amt
+    } else {
+        let entered = editor.readline(format!("Enter amount of {} to sell: ", asset).as_str())?;
+        entered.parse()?
+    };
+    let sold_units = U256::from(ethers::utils::parse_units(sold, decimals as u32)?);
+    if sold_units > our_balance {
+        println!("We don't have enough {} to sell this amount! wanted {}, but we have {}",
+            asset,
+            Paint::red(format_bought(sold_units.as_u128(), decimals)),
+            Paint::red(format_bought(our_balance.as_u128(), decimals))
+        );
+        return Ok(());
+    }
+    let bought = if let Some(amt) = args.buy {
+        amt
+    } else {
+        let entered = editor.readline("Enter amount of BTC to buy: ")?;
+        entered.parse()?
+    };
+    let lockup = if let Some(time) = args.lockup_time {
+        time
+    } else {
+        let entered = editor.readline(format!("Enter lockup time, e.g. 12h, or in Eth blocks [default: {}]: ", timelock::DEFAULT_ETH_LOCKUP).as_str())?;
+        if entered.is_empty() {
+            timelock::DEFAULT_ETH_LOCKUP
+        } else {
+            entered.parse()?
+        }
+    };
+    let lockup = lockup.eth_blocks();
+    timelock::check_eth(lockup)?;
+    println!();
+    println!("Selling {}{} for {}BTC", sold, asset, bought);
+    println!("Blocks your {} will be locked up in the swap contract for: {} (about {})", asset, lockup, timelock::format_duration(timelock::eth_duration(lockup)));
+
+    println!();
+
+    let bought_sats: u128 = Amount::from_btc(bought)?.to_sat() as u128;
+    price::check_offer_rate(state, bought_sats, sold_units.as_u128(), decimals, args.token, args.ignore_price).await?;
+
+    let expiry = expiry::for_new_offer(args.expires_in, args.expires_at_height)?;
+    println!("Offer expires at {}", expiry);
+
+    // the offer's amounts are named from the BTC side like the rest of the swap, so sold is the BTC
+    let offer = EthOfferRequest {
+        version: offer::VERSION,
+        sold: bought_sats,
+        bought: sold_units.as_u128(),
+        lockup_eth: lockup,
+        buyer_pubkey_hash: state.our_pubkey_hash()?,
+        buyer_eth_address: eth_address.to_fixed_bytes(),
+        token_address: args.token.map(|token| token.to_fixed_bytes()),
+        token_decimals: decimals,
+        relayed: args.relayed,
+        expiry: Some(expiry),
+    };
+
+    state.db.add_pending_eth_offer(&message::request_hash(&offer)?, &offer)?;
+
+    let message = Message::EthRequest(offer);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "offer").await?;
+    Ok(())
+}
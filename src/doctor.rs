@@ -0,0 +1,44 @@
+use clap::Parser;
+use ethers::providers::Middleware;
+use ethers::utils::hex;
+use yansi::Paint;
+
+use crate::state::GauloiState;
+
+/// Check the configured RPC and GauloiFactory contract before trading
+#[derive(Parser, Debug)]
+pub struct DoctorArgs {
+
+}
+
+pub async fn doctor(state: &mut GauloiState, _args: DoctorArgs) -> Result<(), anyhow::Error> {
+    println!();
+    println!("{}", Paint::yellow("=== Gauloi doctor ==="));
+
+    let chain_id = state.eth_api.client.get_chainid().await?;
+    let (rpc_count, quorum) = state.eth_api.quorum();
+    println!("ETH RPCs: {} configured, {} needed to agree on critical reads", rpc_count, quorum);
+    let block = state.eth_api.tip_height().await?;
+    println!("ETH chain id {}, latest block {}", chain_id, block);
+
+    let check = state.eth_api.check_contract().await?;
+    println!("GauloiFactory address: {}", hex::encode_prefixed(check.address));
+    println!("Contract deployed: {}", ok_or_fail(check.has_code));
+    println!("Bytecode matches bundled GauloiFactory: {}", ok_or_fail(check.code_matches));
+    println!("Swap creation active: {}", ok_or_fail(check.is_active));
+
+    println!();
+    match check.ensure_tradeable() {
+        Ok(()) => println!("{}", Paint::green("Ready to trade")),
+        Err(e) => println!("{} {}", Paint::red("Refusing to trade:"), e),
+    }
+    Ok(())
+}
+
+fn ok_or_fail(ok: bool) -> Paint<&'static str> {
+    if ok {
+        Paint::green("yes")
+    } else {
+        Paint::red("no")
+    }
+}
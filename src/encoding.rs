@@ -0,0 +1,93 @@
+//! Checksummed bech32m text encoding for messages, with a prefix saying what kind of message it is
+
+use anyhow::Result;
+use bech32::{FromBase32, ToBase32, Variant};
+use thiserror::Error;
+
+use crate::message::Message;
+
+pub const REQUEST_HRP: &str = "gauloioffer";
+pub const RESPONSE_HRP: &str = "gauloiresp";
+pub const CANCEL_HRP: &str = "gauloicancel";
+pub const COUNTER_HRP: &str = "gauloicounter";
+pub const ACCEPT_HRP: &str = "gauloiaccept";
+pub const FILL_HRP: &str = "gauloifill";
+
+#[derive(Error, Debug)]
+pub enum EncodingError {
+    #[error("The message's checksum doesn't match, it may have been cut off or mistyped")]
+    BadChecksum,
+    #[error("The message isn't bech32m encoded: {0}")]
+    Bech32(bech32::Error),
+    #[error("Unknown message prefix {0}")]
+    UnknownPrefix(String),
+    #[error("This is an offer {got}, {hint}")]
+    WrongKind { got: &'static str, hint: &'static str },
+    #[error("This isn't a gauloi message or legacy hex")]
+    Unrecognised,
+}
+
+fn hrp_for(kind: &str) -> &'static str {
+    match kind {
+        "request" => REQUEST_HRP,
+        "response" => RESPONSE_HRP,
+        "counter" => COUNTER_HRP,
+        "accept" => ACCEPT_HRP,
+        "fill" => FILL_HRP,
+        _ => CANCEL_HRP,
+    }
+}
+
+fn kind_for(hrp: &str) -> Option<&'static str> {
+    match hrp {
+        REQUEST_HRP => Some("request"),
+        RESPONSE_HRP => Some("response"),
+        CANCEL_HRP => Some("cancel"),
+        COUNTER_HRP => Some("counter"),
+        ACCEPT_HRP => Some("accept"),
+        FILL_HRP => Some("fill"),
+        _ => None,
+    }
+}
+
+/// Where to take a message of each kind
+fn hint_for(kind: &str) -> &'static str {
+    match kind {
+        "request" | "cancel" => "pass it to `parse`",
+        "response" | "fill" => "pass it to `import`",
+        _ => "pass it to `counter`",
+    }
+}
+
+/// Encode the bytes sent for a message, signed or not, with the prefix for the kind of message
+pub fn encode(message: &Message, bytes: &[u8]) -> Result<String> {
+    Ok(bech32::encode(hrp_for(message.kind()), bytes.to_base32(), Variant::Bech32m)?)
+}
+
+/// Decode pasted text into the bytes it carries, with the kind of message its prefix says it is. Legacy hex has
+/// no prefix, so its kind is only known once it's decoded
+pub fn decode(text: &str) -> Result<(Vec<u8>, Option<&'static str>)> {
+    let text = text.trim();
+    if text.to_lowercase().starts_with("gauloi") {
+        let (hrp, data, variant) = bech32::decode(text).map_err(|e| match e {
+            bech32::Error::InvalidChecksum => EncodingError::BadChecksum,
+            e => EncodingError::Bech32(e),
+        })?;
+        if variant != Variant::Bech32m {
+            return Err(EncodingError::BadChecksum.into());
+        }
+        let kind = kind_for(&hrp).ok_or(EncodingError::UnknownPrefix(hrp.clone()))?;
+        Ok((Vec::<u8>::from_base32(&data)?, Some(kind)))
+    } else {
+        let bytes = hex::decode(text).map_err(|_| EncodingError::Unrecognised)?;
+        Ok((bytes, None))
+    }
+}
+
+/// [decode] text that should be a message of one of the `expected` kinds, pointing to the right command if it isn't
+pub fn decode_as(text: &str, expected: &[&'static str]) -> Result<Vec<u8>> {
+    match decode(text)? {
+        (_, Some(kind)) if !expected.contains(&kind) => Err(EncodingError::WrongKind { got: kind, hint: hint_for(kind) }.into()),
+        (bytes, _) => Ok(bytes),
+    }
+}
@@ -0,0 +1,198 @@
+//! Signed envelopes for offer requests and responses, so a message can't be forged or tampered with in transit
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+use bitcoin::hashes::{hash160, sha256, Hash};
+use bitcoin::secp256k1::{ecdsa, Message, PublicKey};
+use ethers::signers::Signer;
+use ethers::types::{Signature, H160};
+use serde::de::{SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::encoding;
+use crate::message;
+use crate::qr::{self, QrArgs};
+use crate::state::GauloiState;
+
+#[derive(Error, Debug)]
+pub enum EnvelopeError {
+    #[error("The message isn't signed, pass --allow-unsigned to accept it anyway")]
+    Unsigned,
+    #[error("The BTC key the message is signed with doesn't match its pubkey hash")]
+    WrongBtcKey,
+    #[error("The BTC signature doesn't match the message")]
+    BadBtcSignature,
+    #[error("The message is signed by ETH address {0:?} instead of its sender's")]
+    WrongEthSigner(H160),
+}
+
+/// An offer message with its sender's signatures over the message's CBOR bytes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedEnvelope {
+    /// CBOR of the [crate::offer::OfferRequest] or [crate::offer::OfferResponse]
+    #[serde(with = "cbor_bytes")]
+    pub message: Vec<u8>,
+    /// Compressed pubkey of the sender's BTC key, it has to HASH_160 to the pubkey hash in the message
+    #[serde(with = "cbor_bytes")]
+    pub btc_pubkey: Vec<u8>,
+    /// Compact ECDSA signature by the BTC key over SHA256 of the message
+    #[serde(with = "cbor_bytes")]
+    pub btc_signature: Vec<u8>,
+    /// EIP-191 signature by the sender's ETH address over the message, if they signed with it too
+    #[serde(default, with = "cbor_bytes_opt")]
+    pub eth_signature: Option<Vec<u8>>,
+}
+
+impl SignedEnvelope {
+    /// Sign a message with our BTC key, and with our ETH key too if `sign_eth` is set
+    pub async fn seal(state: &GauloiState, message: Vec<u8>, sign_eth: bool) -> Result<Self> {
+        let digest = Message::from_slice(sha256::Hash::hash(&message).as_byte_array())?;
+        let btc_key = state.btc_signing_key()?;
+        let btc_signature = state.secp.sign_ecdsa(&digest, &btc_key);
+
+        let eth_signature = if sign_eth {
+            Some(state.get_wallet()?.sign_message(&message).await?.to_vec())
+        } else {
+            None
+        };
+
+        Ok(SignedEnvelope {
+            message,
+            btc_pubkey: btc_key.public_key(&state.secp).serialize().to_vec(),
+            btc_signature: btc_signature.serialize_compact().to_vec(),
+            eth_signature,
+        })
+    }
+
+    /// Seal a message and print it for the other side under `label`, and as a QR code if `qr` asks for one
+    pub async fn publish(state: &GauloiState, message: &message::Message, sign_eth: bool, qr: &QrArgs, label: &str) -> Result<()> {
+        let envelope = SignedEnvelope::seal(state, message.encode()?, sign_eth).await?;
+        let encoded = encoding::encode(message, &envelope.to_cbor()?)?;
+        println!("{}:\n{}", label, encoded);
+        qr::output_message(qr, &encoded)
+    }
+
+    /// Check the message is signed by the BTC key with `pubkey_hash`, and by `eth_address` if it has an ETH
+    /// signature, returning whether it did
+    pub fn verify(&self, state: &GauloiState, pubkey_hash: [u8; 20], eth_address: [u8; 20]) -> Result<bool> {
+        let btc_pubkey = PublicKey::from_slice(&self.btc_pubkey)?;
+        if hash160::Hash::hash(&self.btc_pubkey).to_byte_array() != pubkey_hash {
+            return Err(EnvelopeError::WrongBtcKey.into());
+        }
+        let digest = Message::from_slice(sha256::Hash::hash(&self.message).as_byte_array())?;
+        let btc_signature = ecdsa::Signature::from_compact(&self.btc_signature)?;
+        if state.secp.verify_ecdsa(&digest, &btc_signature, &btc_pubkey).is_err() {
+            return Err(EnvelopeError::BadBtcSignature.into());
+        }
+
+        match &self.eth_signature {
+            Some(eth_signature) => {
+                let signer = Signature::try_from(eth_signature.as_slice())?.recover(self.message.as_slice())?;
+                if signer != H160::from(eth_address) {
+                    return Err(EnvelopeError::WrongEthSigner(signer).into());
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn to_cbor(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Split pasted bytes into the message and its envelope, bytes that aren't an envelope are taken as an unsigned
+/// message from before offers were signed
+pub fn open(bytes: Vec<u8>) -> (Vec<u8>, Option<SignedEnvelope>) {
+    match ciborium::from_reader::<SignedEnvelope, _>(bytes.as_slice()) {
+        Ok(envelope) => (envelope.message.clone(), Some(envelope)),
+        Err(_) => (bytes, None),
+    }
+}
+
+/// Check a message's envelope against the sender's keys in the message, failing for unsigned messages unless
+/// `allow_unsigned` is set
+pub fn check_sender(
+    state: &GauloiState,
+    envelope: Option<&SignedEnvelope>,
+    pubkey_hash: [u8; 20],
+    eth_address: [u8; 20],
+    allow_unsigned: bool,
+) -> Result<()> {
+    match envelope {
+        Some(envelope) => {
+            let eth_signed = envelope.verify(state, pubkey_hash, eth_address)?;
+            if eth_signed {
+                println!("Signed by the sender's BTC key and ETH address");
+            } else {
+                println!("Signed by the sender's BTC key");
+            }
+            Ok(())
+        }
+        None if allow_unsigned => {
+            println!("{} the message isn't signed, its sender can't be checked", yansi::Paint::yellow("Warning:"));
+            Ok(())
+        }
+        None => bail!(EnvelopeError::Unsigned),
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a byte string")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Serialize byte vectors as CBOR byte strings instead of arrays of integers
+mod cbor_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+}
+
+/// [cbor_bytes] for optional fields
+mod cbor_bytes_opt {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "cbor_bytes")] Vec<u8>);
+
+    pub fn serialize<S: Serializer>(bytes: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        bytes.clone().map(Wrapper).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|wrapper| wrapper.0))
+    }
+}
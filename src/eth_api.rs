@@ -1,20 +1,38 @@
 //! Networked API functions for calling out to an eth client, interacting with the EVM Gauloi smart contract
 
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::bail;
+use bitcoin::hashes::{sha256, Hash};
 use ethers::{
-    abi::{Tokenizable, Tokenize},
+    abi::{self, AbiDecode, Detokenize, Token, Tokenizable},
     contract::abigen,
     prelude::{
         Address,
         k256::ecdsa::SigningKey, MiddlewareBuilder,
     },
-    providers::{Http, Middleware, Provider},
+    providers::{Middleware, Provider},
     signers::Signer,
     types::U256,
 };
 use ethers::contract::ContractCall;
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::{keccak256, rlp};
+use futures::future::join_all;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::config::EthChainConfig;
+use crate::eth_transport::EthTransport;
+use crate::offer::{EthTxKind, Offer, PendingEthTx};
+use crate::swaps::SwapStorage;
 
 // Generate the code from the deployed ABI files
 abigen!(
@@ -23,10 +41,552 @@ abigen!(
     derives(serde::Deserialize, serde::Serialize),
 );
 
+/// Bindings for the ERC-20 version of the factory and the tokens it locks, kept in their own module as the
+/// generated call types share names with [GauloiFactory]'s
+pub mod token {
+    use ethers::contract::abigen;
+
+    abigen!(
+        GauloiTokenFactory,
+        "./src/abi/GauloiTokenFactory.json",
+        derives(serde::Deserialize, serde::Serialize);
+
+        Erc20,
+        r#"[
+            function approve(address spender, uint256 amount) external returns (bool)
+            function allowance(address owner, address spender) external view returns (uint256)
+            function balanceOf(address account) external view returns (uint256)
+            function decimals() external view returns (uint8)
+            function symbol() external view returns (string)
+        ]"#;
+    );
+}
+
+/// Bindings for the version of the factory that can be claimed through a relayer, apart from
+/// `claimSwapRelayed` its interface is the same as [GauloiFactory]'s so the rest is called through those bindings
+pub mod relay {
+    use ethers::contract::abigen;
+
+    abigen!(
+        GauloiRelayFactory,
+        "./src/abi/GauloiRelayFactory.json",
+        derives(serde::Deserialize, serde::Serialize),
+    );
+}
+
+/// A BTC seller's signed authorization for a relayer to claim their ETH from a [relay::GauloiRelayFactory] swap,
+/// keeping `fee` for the gas it pays
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RelayedClaim {
+    pub swap_id: U256,
+    pub preimage: [u8; 32],
+    pub fee: U256,
+    /// Only this relayer can submit the claim, anyone can if it's zero
+    pub relayer: Address,
+    pub signature: Bytes,
+}
+
+/// A swap found by [EthApi::scan_swaps]
+#[derive(Debug, Clone)]
+pub struct ScannedSwap {
+    pub contract: SwapContract,
+    pub swap_id: U256,
+    pub swap: LockedSwap,
+}
+
+/// Bindings for Chainlink-style price feed aggregators
+pub mod feed {
+    use ethers::contract::abigen;
+
+    abigen!(
+        Aggregator,
+        r#"[
+            function decimals() external view returns (uint8)
+            function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+        ]"#;
+    );
+}
+
+/// Which factory the ETH side of a swap is locked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapContract {
+    /// Native ETH in the [GauloiFactory]
+    Gauloi,
+    /// Native ETH in the [relay::GauloiRelayFactory], so the BTC seller can claim it through a relayer
+    Relay,
+    /// ERC-20 tokens in the [token::GauloiTokenFactory]
+    Token(Address),
+}
+
+impl SwapContract {
+    /// The kind of factory the swap is locked in
+    pub fn factory(&self) -> Factory {
+        match self {
+            SwapContract::Gauloi => Factory::Gauloi,
+            SwapContract::Relay => Factory::Relay,
+            SwapContract::Token(_) => Factory::Token,
+        }
+    }
+}
+
+/// The factory contracts swaps can be locked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Factory {
+    Gauloi,
+    Relay,
+    Token,
+}
+
+impl Factory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Factory::Gauloi => "GauloiFactory",
+            Factory::Relay => "GauloiRelayFactory",
+            Factory::Token => "GauloiTokenFactory",
+        }
+    }
+
+    /// The bundled artifact of the factory, used to verify the deployed bytecode and to deploy it
+    fn artifact(&self) -> &'static str {
+        match self {
+            Factory::Gauloi => GAULOI_ARTIFACT,
+            Factory::Relay => RELAY_ARTIFACT,
+            Factory::Token => TOKEN_ARTIFACT,
+        }
+    }
+}
+
+/// A swap as either factory stores it, along with the token it locks, `None` for ETH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedSwap {
+    pub buyer: Address,
+    pub seller: Address,
+    pub token: Option<Address>,
+    pub value: U256,
+    pub preimage_hash: [u8; 32],
+    pub preimage: [u8; 32],
+    pub unlock_block: U256,
+    pub complete: bool,
+}
+
+impl From<Swap> for LockedSwap {
+    fn from(swap: Swap) -> Self {
+        LockedSwap {
+            buyer: swap.buyer,
+            seller: swap.seller,
+            token: None,
+            value: swap.value,
+            preimage_hash: swap.preimage_hash,
+            preimage: swap.preimage,
+            unlock_block: swap.unlock_block,
+            complete: swap.complete,
+        }
+    }
+}
+
+impl From<token::TokenSwap> for LockedSwap {
+    fn from(swap: token::TokenSwap) -> Self {
+        LockedSwap {
+            buyer: swap.buyer,
+            seller: swap.seller,
+            token: Some(swap.token),
+            value: swap.value,
+            preimage_hash: swap.preimage_hash,
+            preimage: swap.preimage,
+            unlock_block: swap.unlock_block,
+            complete: swap.complete,
+        }
+    }
+}
+
+/// The bundled artifacts of each factory, built from `contracts/` with forge (see `foundry.toml`)
+const GAULOI_ARTIFACT: &str = include_str!("./abi/GauloiFactory.json");
+const RELAY_ARTIFACT: &str = include_str!("./abi/GauloiRelayFactory.json");
+const TOKEN_ARTIFACT: &str = include_str!("./abi/GauloiTokenFactory.json");
+
+/// Reasons the contract at the configured address can't be traded against
+#[derive(Error, Debug)]
+pub enum ContractCheckError {
+    #[error("No contract is deployed at {0:?}")]
+    NoCode(Address),
+    #[error("The contract at {0:?} doesn't match the bundled {1} bytecode")]
+    BytecodeMismatch(Address, &'static str),
+    #[error("The factory at {0:?} is paused, swaps can't be created until its owner resumes it")]
+    Inactive(Address),
+    #[error("No compiled {0} is bundled to check the deployed contract against, build it with `forge build` and copy out/{0}.sol/{0}.json to src/abi")]
+    NoBundledBytecode(&'static str),
+    #[error("No GauloiTokenFactory is configured, set `token_gauloi_address` under [eth] in the chain config")]
+    NoTokenFactory,
+    #[error("No GauloiRelayFactory is configured, set `relay_gauloi_address` under [eth] in the chain config")]
+    NoRelayFactory,
+}
+
+#[derive(Error, Debug)]
+#[error("The offer gives token {token:?} {offered} decimals but it has {actual}, so its amounts are off, refusing it")]
+pub struct TokenDecimalsError {
+    pub token: Address,
+    pub offered: u8,
+    pub actual: u8,
+}
+
+/// Readable reasons for a [GauloiFactory] call reverting, decoded from the contract's `require` messages
+#[derive(Error, Debug)]
+pub enum GauloiRevert {
+    #[error("Swap already claimed or refunded")]
+    AlreadyComplete,
+    #[error("Wrong preimage for this swap")]
+    WrongPreimage,
+    #[error("Swap is not yet unlockable, wait for the timeout block")]
+    NotYetUnlockable,
+    #[error("Contract inactive, swaps can't be created")]
+    Inactive,
+    #[error("Swap has no value")]
+    NoValue,
+    #[error("Locking the tokens failed, check the token balance and allowance")]
+    TokenTransferFailed,
+    #[error("No swap exists with this ID")]
+    UnknownSwap,
+    #[error("Sending the swap's ETH to the recipient failed")]
+    TransferFailed,
+    #[error("Only the contract owner can do this")]
+    Unauthorized,
+    #[error("Reverted: {0}")]
+    Other(String),
+}
+
+impl GauloiRevert {
+    /// Selector of the `Error(string)` revert `require` produces
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+    fn from_reason(reason: &str) -> Self {
+        match reason {
+            "swap is already completed" => GauloiRevert::AlreadyComplete,
+            "preimage hash doesn't match" => GauloiRevert::WrongPreimage,
+            "swap not ready to claim" => GauloiRevert::NotYetUnlockable,
+            "creating swaps isn't active" => GauloiRevert::Inactive,
+            "creating a swap without any ETH" => GauloiRevert::NoValue,
+            "creating a swap without any tokens" => GauloiRevert::NoValue,
+            "TRANSFER_FROM_FAILED" => GauloiRevert::TokenTransferFailed,
+            "swapId out of bounds" => GauloiRevert::UnknownSwap,
+            "claim wasn't sent to seller" => GauloiRevert::TransferFailed,
+            "UNAUTHORIZED" => GauloiRevert::Unauthorized,
+            other => GauloiRevert::Other(other.to_string()),
+        }
+    }
+
+    /// Decode the revert data returned by a failed call
+    pub fn from_revert_data(data: &[u8]) -> Self {
+        if data.len() < 4 || data[..4] != Self::ERROR_SELECTOR {
+            return GauloiRevert::Other(format!("without a reason (0x{})", hex::encode(data)));
+        }
+        match ethers::abi::decode(&[ethers::abi::ParamType::String], &data[4..]) {
+            Ok(tokens) => match tokens.into_iter().next().and_then(|token| token.into_string()) {
+                Some(reason) => GauloiRevert::from_reason(&reason),
+                None => GauloiRevert::Other(format!("0x{}", hex::encode(data))),
+            },
+            Err(_) => GauloiRevert::Other(format!("0x{}", hex::encode(data))),
+        }
+    }
+}
+
+/// Turn a failed contract call into a [GauloiRevert] if the call reverted
+fn decode_call_error<M: Middleware + 'static>(e: ContractError<M>) -> anyhow::Error {
+    match e.as_revert() {
+        Some(data) => GauloiRevert::from_revert_data(data).into(),
+        None => e.into(),
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("Transaction {tx_hash:?} was mined but failed: {reason}")]
+pub struct FailedTransactionError {
+    pub tx_hash: H256,
+    pub reason: String,
+}
+
+#[derive(Error, Debug)]
+#[error("{kind:?} transaction with nonce {nonce} has to be signed offline, submit it with `eth-broadcast <signed-hex>` and run this again")]
+pub struct AwaitingSignatureError {
+    pub kind: EthTxKind,
+    pub nonce: u64,
+}
+
+/// Bail if a receipt shows the transaction was mined but reverted
+fn ensure_succeeded(receipt: Option<TransactionReceipt>) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+    if let Some(receipt) = &receipt {
+        if receipt.status == Some(U64::zero()) {
+            return Err(FailedTransactionError {
+                tx_hash: receipt.transaction_hash,
+                reason: "no revert reason available".to_string(),
+            }.into());
+        }
+    }
+    Ok(receipt)
+}
+
+/// Not enough of the configured RPCs agreed on a critical read
+#[derive(Error, Debug)]
+#[error("Only {agreeing} of {total} ETH RPCs agreed on the {what}, {quorum} needed")]
+pub struct QuorumError {
+    pub what: String,
+    pub agreeing: usize,
+    pub total: usize,
+    pub quorum: usize,
+}
+
+/// The configured RPCs split evenly between the top answers to a critical read, so there's no telling which is right
+#[derive(Error, Debug)]
+#[error("ETH RPCs are split on the {what}, {agreeing} for each of the top answers")]
+pub struct QuorumTieError {
+    pub what: String,
+    pub agreeing: usize,
+}
+
+/// Result of checking the contract at the configured address against the bundled GauloiFactory artifact
+#[derive(Debug, Clone, Copy)]
+pub struct ContractCheck {
+    pub factory: Factory,
+    pub address: Address,
+    /// Whether there is any code at the address at all
+    pub has_code: bool,
+    /// Whether the code matches the bundled deployed bytecode (ignoring metadata and immutables)
+    pub code_matches: bool,
+    /// Result of `is_active()`, only queried if the code matches
+    pub is_active: bool,
+}
+
+impl ContractCheck {
+    /// Turn the check into an error if the contract isn't the bundled one. Swaps already in a paused contract can
+    /// still be claimed and refunded, so this doesn't look at whether it's active
+    pub fn ensure_genuine(&self) -> Result<(), ContractCheckError> {
+        if !self.has_code {
+            return Err(ContractCheckError::NoCode(self.address));
+        }
+        if !self.code_matches {
+            return Err(ContractCheckError::BytecodeMismatch(self.address, self.factory.name()));
+        }
+        Ok(())
+    }
+
+    /// Turn the check into an error if we shouldn't create swaps in this contract
+    pub fn ensure_tradeable(&self) -> Result<(), ContractCheckError> {
+        self.ensure_genuine()?;
+        if !self.is_active {
+            return Err(ContractCheckError::Inactive(self.address));
+        }
+        Ok(())
+    }
+}
+
+/// Strip the CBOR encoded metadata solc appends to the runtime code, its length is the last 2 bytes (big endian)
+fn strip_metadata(code: &[u8]) -> &[u8] {
+    if code.len() < 2 {
+        return code;
+    }
+    let metadata_len = u16::from_be_bytes([code[code.len() - 2], code[code.len() - 1]]) as usize;
+    if metadata_len + 2 > code.len() {
+        return code;
+    }
+    &code[..code.len() - metadata_len - 2]
+}
+
+/// Hex-decode a code object from a bundled artifact, `None` if the artifact only has the ABI
+fn artifact_code(artifact: &str, field: &str) -> Option<Vec<u8>> {
+    let artifact: serde_json::Value = serde_json::from_str(artifact).ok()?;
+    let object = artifact[field]["object"].as_str()?;
+    hex::decode(object.trim_start_matches("0x")).ok().filter(|code| !code.is_empty())
+}
+
+/// The `(start, length)` ranges of immutables in the bundled runtime code, these are filled in at deploy time
+fn immutable_references(artifact: &str) -> Vec<(usize, usize)> {
+    let artifact: serde_json::Value = match serde_json::from_str(artifact) {
+        Ok(artifact) => artifact,
+        Err(_) => return Vec::new(),
+    };
+    let mut references = Vec::new();
+    if let Some(immutables) = artifact["deployedBytecode"]["immutableReferences"].as_object() {
+        for ranges in immutables.values().filter_map(|ranges| ranges.as_array()) {
+            for range in ranges {
+                let start = range["start"].as_u64().unwrap_or_default() as usize;
+                let length = range["length"].as_u64().unwrap_or_default() as usize;
+                references.push((start, length));
+            }
+        }
+    }
+    references
+}
+
+/// The account a filled in or signed transaction is sent from
+fn sender_of(tx: &TypedTransaction) -> [u8; 20] {
+    tx.from().cloned().unwrap_or_default().to_fixed_bytes()
+}
+
+/// Compare deployed runtime code against the expected runtime code, ignoring the metadata hash and immutables
+pub fn bytecode_matches(deployed: &[u8], expected: &[u8], artifact: &str) -> bool {
+    let mut deployed = strip_metadata(deployed).to_vec();
+    let mut expected = strip_metadata(expected).to_vec();
+    if deployed.len() != expected.len() {
+        return false;
+    }
+    for (start, length) in immutable_references(artifact) {
+        let end = (start + length).min(deployed.len());
+        if start >= end {
+            continue;
+        }
+        deployed[start..end].fill(0);
+        expected[start..end].fill(0);
+    }
+    deployed == expected
+}
+
+/// How often to poll the txpool when the RPC doesn't support pending transaction filters
+const TXPOOL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait between checks of the chain when the RPC can't push new blocks
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Pull the preimage out of a pending `claimSwap` of our swap, checking it against the preimage-hash
+fn claimed_preimage(tx: &Transaction, factory: Address, swap_id: U256, preimage_hash: [u8; 32]) -> Option<[u8; 32]> {
+    if tx.to != Some(factory) {
+        return None;
+    }
+    // every factory has the same claimSwap(uint256,bytes32) signature
+    let (claimed_swap_id, preimage) = match ClaimSwapCall::decode(&tx.input) {
+        Ok(claim) => (claim.swap_id, claim.preimage),
+        Err(_) => {
+            let claim = relay::ClaimSwapRelayedCall::decode(&tx.input).ok()?;
+            (claim.swap_id, claim.preimage)
+        }
+    };
+    if claimed_swap_id != swap_id || sha256::Hash::hash(&preimage).to_byte_array() != preimage_hash {
+        return None;
+    }
+    Some(preimage)
+}
+
+/// How many blocks an RPC can be behind the quorum tip before it's reported as lagging
+const MAX_RPC_LAG: u64 = 2;
+
+/// How long to wait for a sent transaction to be mined before asking the user to speed it up
+const TX_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// What happened to a transaction we had stored as pending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingTxState {
+    /// One of the transactions sent with the nonce was mined
+    Mined(H256),
+    /// The nonce was used by a transaction we didn't send through the CLI
+    Replaced,
+    /// Nothing with the nonce has been mined yet
+    Pending,
+}
+
+/// Bump a fee by 25%, comfortably over the 10% nodes require to replace a transaction
+fn bump_fee(fee: U256) -> U256 {
+    fee + fee / 4 + 1
+}
+
+/// How old a price feed answer can be before it's not trusted, Chainlink's BTC/ETH feed updates at least daily
+const MAX_PRICE_FEED_AGE: u64 = 25 * 60 * 60;
+
+/// How many reads to put in each Multicall3 batch when scanning the factories
+const MULTICALL_BATCH_SIZE: usize = 100;
+
+/// How many blocks a private transaction stays valid for on the private endpoint
+const PRIVATE_TX_MAX_BLOCKS: u64 = 25;
+
+/// Endpoint that takes signed transactions through Flashbots-style `eth_sendPrivateTransaction`, keeping them
+/// out of the public mempool until they're mined
+struct PrivateRpc {
+    url: String,
+    /// How long to wait for a private transaction to be mined before sending it publicly
+    fallback: Duration,
+    /// Throwaway key the requests are signed with, relays use it to identify the sender
+    auth: LocalWallet,
+}
+
+impl PrivateRpc {
+    async fn send(&self, raw: &Bytes, max_block: U64) -> Result<(), anyhow::Error> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendPrivateTransaction",
+            "params": [{ "tx": raw, "maxBlockNumber": max_block }],
+        }).to_string();
+        let body_hash = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+        let signature = self.auth.sign_message(body_hash).await?;
+
+        let response: serde_json::Value = reqwest::Client::new()
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .header("X-Flashbots-Signature", format!("{:?}:0x{}", self.auth.address(), signature))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            bail!("{}", error)
+        }
+        Ok(())
+    }
+}
+
+/// Client that signs transactions with the local ETH wallet
+type SignedClient = SignerMiddleware<Arc<Provider<EthTransport>>, Wallet<SigningKey>>;
+
+/// Who sends a swap transaction
+#[derive(Clone)]
+pub enum EthSender {
+    /// Sign and send it with a local wallet
+    Wallet(Wallet<SigningKey>),
+    /// Export it unsigned for an air-gapped account to sign, see [EthApi::broadcast_signed]
+    Offline(Address),
+}
+
+impl EthSender {
+    pub fn address(&self) -> Address {
+        match self {
+            EthSender::Wallet(wallet) => wallet.address(),
+            EthSender::Offline(address) => *address,
+        }
+    }
+}
+
+/// Waits for the next block on a single subscription kept for as long as the waiter, so blocks mined between two
+/// waits aren't missed
+pub struct BlockWaiter<'a> {
+    blocks: Option<SubscriptionStream<'a, EthTransport, Block<H256>>>,
+}
+
+impl BlockWaiter<'_> {
+    /// Wait until the next block is mined, falling back to a fixed sleep if the subscription has ended
+    pub async fn next(&mut self) {
+        if let Some(blocks) = &mut self.blocks {
+            if blocks.next().await.is_some() {
+                return;
+            }
+            self.blocks = None;
+        }
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+    }
+}
+
 /// Ethereum API client and associated contract address
 pub struct EthApi {
-    pub client: Arc<Provider<Http>>,
+    pub client: Arc<Provider<EthTransport>>,
+    /// Every configured endpoint (including [EthApi::client]) with its URL, for quorum reads
+    read_clients: Vec<(String, Arc<Provider<EthTransport>>)>,
+    /// How many of the [EthApi::read_clients] have to agree on a critical read
+    quorum: usize,
     gauloi_address: Address,
+    /// The [token::GauloiTokenFactory] used for ERC-20 swaps, if there is one on this chain
+    token_gauloi_address: Option<Address>,
+    /// The [relay::GauloiRelayFactory] for swaps claimed through a relayer, if there is one on this chain
+    relay_gauloi_address: Option<Address>,
+    /// Where commits and claims are sent instead of the public mempool, if configured
+    private_rpc: Option<PrivateRpc>,
 }
 
 impl EthApi {
@@ -48,97 +608,1721 @@ impl EthApi {
     /// Shortcut to build an [EthApi] instance on Sepolia using a public API pointing to
     /// the Sepolia Gauloi contract
     pub fn testnet() -> Self {
-        let client =
-            Provider::try_from("https://eth-sepolia.public.blastapi.io".to_string()).unwrap();
+        let rpc_url = "https://eth-sepolia.public.blastapi.io";
+        let client = Arc::new(Provider::new(EthTransport::Http(rpc_url.parse().unwrap())));
         EthApi {
-            client: Arc::new(client),
+            read_clients: vec![(rpc_url.to_string(), client.clone())],
+            quorum: 1,
+            client,
             gauloi_address: EthApi::testnet_gauloi(),
+            token_gauloi_address: None,
+            relay_gauloi_address: None,
+            private_rpc: None,
         }
     }
 
     /// Shortcut to build an [EthApi] instance using a public API pointing to the mainnet Gauloi contract
     pub fn mainnet() -> Self {
-        let client = Provider::try_from("https://eth.llamarpc.com".to_string()).unwrap();
+        let rpc_url = "https://eth.llamarpc.com";
+        let client = Arc::new(Provider::new(EthTransport::Http(rpc_url.parse().unwrap())));
         EthApi {
-            client: Arc::new(client),
+            read_clients: vec![(rpc_url.to_string(), client.clone())],
+            quorum: 1,
+            client,
             gauloi_address: EthApi::mainnet_gauloi(),
+            token_gauloi_address: None,
+            relay_gauloi_address: None,
+            private_rpc: None,
+        }
+    }
+
+    /// Build an [EthApi] instance for the RPCs and [GauloiFactory] address in the chain config, each endpoint
+    /// can be an HTTP or WebSocket URL or an IPC socket path
+    pub async fn from_config(config: &EthChainConfig) -> Result<Self, anyhow::Error> {
+        let client = Arc::new(Provider::new(EthTransport::connect(&config.rpc_url).await?));
+        let mut read_clients = vec![(config.rpc_url.clone(), client.clone())];
+        for rpc_url in &config.extra_rpc_urls {
+            read_clients.push((rpc_url.clone(), Arc::new(Provider::new(EthTransport::connect(rpc_url).await?))));
         }
+        let quorum = config.quorum.unwrap_or(read_clients.len() / 2 + 1);
+        if quorum == 0 || quorum > read_clients.len() {
+            bail!("Quorum of {} isn't possible with {} ETH RPCs configured", quorum, read_clients.len());
+        }
+
+        Ok(EthApi {
+            read_clients,
+            quorum,
+            client,
+            gauloi_address: config.gauloi_address,
+            token_gauloi_address: config.token_gauloi_address,
+            relay_gauloi_address: config.relay_gauloi_address,
+            private_rpc: config.private_rpc_url.as_ref().map(|url| PrivateRpc {
+                url: url.clone(),
+                fallback: Duration::from_secs(config.private_fallback_secs),
+                auth: LocalWallet::new(&mut rand::thread_rng()),
+            }),
+        })
     }
 
     /// Shortcut to build an [EthApi] instance using a public API, passing in a [GauloiFactory] compatible
     /// contract address on Ethereum mainnet
     pub fn new_mainnet(gauloi_address: &str) -> Self {
-        let client = Provider::try_from("https://eth.llamarpc.com".to_string()).unwrap();
+        let rpc_url = "https://eth.llamarpc.com";
+        let client = Arc::new(Provider::new(EthTransport::Http(rpc_url.parse().unwrap())));
         EthApi {
-            client: Arc::new(client),
+            read_clients: vec![(rpc_url.to_string(), client.clone())],
+            quorum: 1,
+            client,
             gauloi_address: gauloi_address.parse::<Address>().unwrap(),
+            token_gauloi_address: None,
+            relay_gauloi_address: None,
+            private_rpc: None,
         }
     }
 
-    /// Get the currently available ETH balance of the supplied address using the network defined in the [EthApi] client instance
-    pub async fn get_balance(&self, address: &Address) -> Result<U256, Box<dyn std::error::Error>> {
-        let current_balance = self.client.get_balance(address.clone(), None).await?;
-        Ok(current_balance)
+    /// The address of the Gauloi contract this client is pointed at
+    pub fn gauloi_address(&self) -> Address {
+        self.gauloi_address
     }
 
-    pub async fn commit_eth(
+    /// The address of the [token::GauloiTokenFactory], failing if there isn't one configured
+    pub fn token_gauloi_address(&self) -> Result<Address, ContractCheckError> {
+        self.token_gauloi_address.ok_or(ContractCheckError::NoTokenFactory)
+    }
+
+    /// The address of the [relay::GauloiRelayFactory], failing if there isn't one configured
+    pub fn relay_gauloi_address(&self) -> Result<Address, ContractCheckError> {
+        self.relay_gauloi_address.ok_or(ContractCheckError::NoRelayFactory)
+    }
+
+    /// The address of the factory swaps through the given contract are locked in
+    pub fn factory_address(&self, contract: SwapContract) -> Result<Address, ContractCheckError> {
+        self.address_of(contract.factory())
+    }
+
+    /// The configured address of a factory
+    pub fn address_of(&self, factory: Factory) -> Result<Address, ContractCheckError> {
+        match factory {
+            Factory::Gauloi => Ok(self.gauloi_address),
+            Factory::Relay => self.relay_gauloi_address(),
+            Factory::Token => self.token_gauloi_address(),
+        }
+    }
+
+    /// The private endpoint to send a transaction of this kind through, only commits and claims are sent privately
+    fn private_rpc_for(&self, kind: EthTxKind) -> Option<&PrivateRpc> {
+        match kind {
+            EthTxKind::Commit | EthTxKind::Claim => self.private_rpc.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Point this client at a different [GauloiFactory] contract
+    pub fn set_gauloi_address(&mut self, gauloi_address: Address) {
+        self.gauloi_address = gauloi_address;
+    }
+
+    /// Wrap the client with a signer for the chain the client is connected to
+    async fn signed_client(&self, signer: Wallet<SigningKey>) -> Result<Arc<SignedClient>, anyhow::Error> {
+        let chain_id = self.client.get_chainid().await?;
+        let signed_client = self.client.clone().with_signer(signer.with_chain_id(chain_id.as_u64()));
+        Ok(Arc::new(signed_client))
+    }
+
+    /// Build a [GauloiFactory] instance that signs with the given wallet
+    async fn signed_gauloi(&self, signer: Wallet<SigningKey>) -> Result<GauloiFactory<SignedClient>, anyhow::Error> {
+        let signed_client = self.signed_client(signer).await?;
+        Ok(GauloiFactory::new(self.gauloi_address, signed_client))
+    }
+
+    /// Deploy a new [GauloiFactory] from the bundled creation bytecode, returning its address
+    pub async fn deploy_gauloi(&self, signer: Wallet<SigningKey>) -> Result<Address, anyhow::Error> {
+        let signed_client = self.signed_client(signer).await?;
+        let deployed = GauloiFactory::deploy(signed_client, ())?
+            .confirmations(1usize)
+            .send()
+            .await?;
+        Ok(deployed.address())
+    }
+
+    /// Deploy a factory from the creation code in its bundled artifact
+    pub async fn deploy_factory(&self, signer: Wallet<SigningKey>, factory: Factory) -> Result<Address, anyhow::Error> {
+        let Some(bytecode) = artifact_code(factory.artifact(), "bytecode") else {
+            return Err(ContractCheckError::NoBundledBytecode(factory.name()).into());
+        };
+        let artifact: serde_json::Value = serde_json::from_str(factory.artifact())?;
+        let abi: ethers::abi::Abi = serde_json::from_value(artifact["abi"].clone())?;
+        let signed_client = self.signed_client(signer).await?;
+        let deployed = ContractFactory::new(abi, bytecode.into(), signed_client)
+            .deploy(())?
+            .confirmations(1usize)
+            .send()
+            .await?;
+        Ok(deployed.address())
+    }
+
+    /// Point this client at a different factory for relayed or token swaps
+    pub fn set_factory_address(&mut self, factory: Factory, address: Address) {
+        match factory {
+            Factory::Gauloi => self.gauloi_address = address,
+            Factory::Relay => self.relay_gauloi_address = Some(address),
+            Factory::Token => self.token_gauloi_address = Some(address),
+        }
+    }
+
+    /// The number of configured RPCs and how many of them have to agree on critical reads
+    pub fn quorum(&self) -> (usize, usize) {
+        (self.read_clients.len(), self.quorum)
+    }
+
+    /// Run the same read against every configured RPC, returning the value a quorum of them agree on.
+    /// Endpoints that fail or disagree with the others are shown as warnings
+    async fn quorum_read<T, F, Fut>(&self, what: &str, read: F) -> Result<T, anyhow::Error>
+    where
+        T: PartialEq + Debug,
+        F: Fn(Arc<Provider<EthTransport>>) -> Fut,
+        Fut: Future<Output = Result<T, anyhow::Error>>,
+    {
+        if self.read_clients.len() == 1 {
+            return read(self.client.clone()).await;
+        }
+
+        let results = join_all(self.read_clients.iter().map(|(_, client)| read(client.clone()))).await;
+
+        // group the endpoints by the value they returned
+        let mut agreed: Vec<(T, Vec<&str>)> = Vec::new();
+        for ((url, _), result) in self.read_clients.iter().zip(results) {
+            match result {
+                Ok(value) => match agreed.iter_mut().find(|(agreed_value, _)| *agreed_value == value) {
+                    Some((_, urls)) => urls.push(url.as_str()),
+                    None => agreed.push((value, vec![url.as_str()])),
+                },
+                Err(e) => println!("{} {} read of the {} failed: {}", Paint::yellow("Warning:"), url, what, e),
+            }
+        }
+        agreed.sort_by_key(|(_, urls)| std::cmp::Reverse(urls.len()));
+
+        if agreed.len() > 1 {
+            println!("{} ETH RPCs disagree on the {}:", Paint::yellow("Warning:"), what);
+            for (value, urls) in &agreed {
+                println!("  {:?} from {}", value, urls.join(", "));
+            }
+        }
+
+        let agreeing = agreed.first().map(|(_, urls)| urls.len()).unwrap_or_default();
+        // with a quorum of half the RPCs or less, two answers can each reach it
+        if agreed.get(1).is_some_and(|(_, urls)| urls.len() == agreeing) {
+            return Err(QuorumTieError { what: what.to_string(), agreeing }.into());
+        }
+        if agreeing < self.quorum {
+            return Err(QuorumError {
+                what: what.to_string(),
+                agreeing,
+                total: self.read_clients.len(),
+                quorum: self.quorum,
+            }.into());
+        }
+        Ok(agreed.remove(0).0)
+    }
+
+    /// Whether the primary RPC is a WebSocket or IPC connection that can push new blocks and transactions
+    pub fn supports_pubsub(&self) -> bool {
+        let transport: &EthTransport = (*self.client).as_ref();
+        transport.supports_pubsub()
+    }
+
+    /// Start waiting on blocks, using one block subscription for every wait when the primary RPC supports it and
+    /// a fixed sleep otherwise
+    pub async fn block_waiter(&self) -> Result<BlockWaiter<'_>, anyhow::Error> {
+        let blocks = if self.supports_pubsub() {
+            Some(self.client.subscribe_blocks().await?)
+        } else {
+            None
+        };
+        Ok(BlockWaiter { blocks })
+    }
+
+    /// The highest block a quorum of the RPCs have reached, lagging endpoints are shown as warnings
+    pub async fn tip_height(&self) -> Result<U64, anyhow::Error> {
+        if self.read_clients.len() == 1 {
+            return Ok(self.client.get_block_number().await?);
+        }
+
+        let results = join_all(self.read_clients.iter().map(|(_, client)| client.get_block_number())).await;
+        let mut heights = Vec::new();
+        for ((url, _), result) in self.read_clients.iter().zip(results) {
+            match result {
+                Ok(height) => heights.push((height, url.as_str())),
+                Err(e) => println!("{} {} read of the tip height failed: {}", Paint::yellow("Warning:"), url, e),
+            }
+        }
+        if heights.len() < self.quorum {
+            return Err(QuorumError {
+                what: "tip height".to_string(),
+                agreeing: heights.len(),
+                total: self.read_clients.len(),
+                quorum: self.quorum,
+            }.into());
+        }
+        heights.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let tip = heights[self.quorum - 1].0;
+        for (height, url) in &heights {
+            if *height + MAX_RPC_LAG < tip {
+                println!("{} {} is lagging at block {} (quorum tip is {})", Paint::yellow("Warning:"), url, height, tip);
+            }
+        }
+        Ok(tip)
+    }
+
+    /// The block to pin quorum reads to so every RPC answers for the same state, or latest if there's only one RPC
+    async fn quorum_block(&self) -> Result<Option<BlockId>, anyhow::Error> {
+        if self.read_clients.len() == 1 {
+            return Ok(None);
+        }
+        Ok(Some(self.tip_height().await?.into()))
+    }
+
+    /// Get the currently available ETH balance of the supplied address, as read by a quorum of the RPCs
+    pub async fn get_balance(&self, address: &Address) -> Result<U256, anyhow::Error> {
+        self.get_balance_of(address, None).await
+    }
+
+    /// Fetch the code at the Gauloi contract address and compare it with the bundled GauloiFactory bytecode,
+    /// then check whether creating swaps is active
+    pub async fn check_contract(&self) -> Result<ContractCheck, anyhow::Error> {
+        self.check_factory(Factory::Gauloi).await
+    }
+
+    /// Fetch the code at a factory's configured address and compare it with its bundled bytecode, then check
+    /// whether creating swaps is active
+    pub async fn check_factory(&self, factory: Factory) -> Result<ContractCheck, anyhow::Error> {
+        let address = self.address_of(factory)?;
+        let Some(expected) = artifact_code(factory.artifact(), "deployedBytecode") else {
+            return Err(ContractCheckError::NoBundledBytecode(factory.name()).into());
+        };
+        let code = self.client.get_code(address, None).await?;
+        let has_code = !code.is_empty();
+        let code_matches = has_code && bytecode_matches(&code, &expected, factory.artifact());
+        let is_active = if code_matches {
+            self.is_active_for(factory).await?
+        } else {
+            false
+        };
+
+        Ok(ContractCheck {
+            factory,
+            address,
+            has_code,
+            code_matches,
+            is_active,
+        })
+    }
+
+    /// Check the contract and bail if it isn't safe to trade against
+    pub async fn ensure_tradeable(&self) -> Result<(), anyhow::Error> {
+        self.check_contract().await?.ensure_tradeable()?;
+        Ok(())
+    }
+
+    /// Check the factory a swap goes through is the bundled one, without caring whether it's paused
+    pub async fn ensure_genuine_for(&self, contract: SwapContract) -> Result<(), anyhow::Error> {
+        self.check_factory(contract.factory()).await?.ensure_genuine()?;
+        Ok(())
+    }
+
+    /// Check the factory a swap goes through is the bundled one and that swaps can be created in it
+    pub async fn ensure_tradeable_for(&self, contract: SwapContract) -> Result<(), anyhow::Error> {
+        self.check_factory(contract.factory()).await?.ensure_tradeable()?;
+        Ok(())
+    }
+
+    /// Whether creating swaps on the [GauloiFactory] is currently active
+    pub async fn is_active(&self) -> Result<bool, anyhow::Error> {
+        let gauloi = GauloiFactory::new(self.gauloi_address, self.client.clone());
+        Ok(gauloi.is_active().call().await?)
+    }
+
+    /// Whether creating swaps is active on a factory
+    pub async fn is_active_for(&self, factory: Factory) -> Result<bool, anyhow::Error> {
+        let address = self.address_of(factory)?;
+        match factory {
+            Factory::Token => {
+                let gauloi = token::GauloiTokenFactory::new(address, self.client.clone());
+                Ok(gauloi.is_active().call().await?)
+            }
+            _ => {
+                let gauloi = GauloiFactory::new(address, self.client.clone());
+                Ok(gauloi.is_active().call().await?)
+            }
+        }
+    }
+
+    /// The symbol and decimals of an ERC-20 token
+    pub async fn token_info(&self, token: Address) -> Result<(String, u8), anyhow::Error> {
+        let erc20 = token::Erc20::new(token, self.client.clone());
+        let symbol = erc20.symbol().call().await?;
+        let decimals = erc20.decimals().call().await?;
+        Ok((symbol, decimals))
+    }
+
+    /// The symbol of an ERC-20 token, failing if it doesn't have the decimals an offer gives for it
+    pub async fn checked_token_symbol(&self, token: Address, decimals: u8) -> Result<String, anyhow::Error> {
+        let (symbol, actual) = self.token_info(token).await?;
+        if actual != decimals {
+            return Err(TokenDecimalsError { token, offered: decimals, actual }.into());
+        }
+        Ok(symbol)
+    }
+
+    /// Get the balance of the supplied address in the given asset, [None] being native ETH
+    pub async fn get_balance_of(&self, address: &Address, token: Option<Address>) -> Result<U256, anyhow::Error> {
+        let block = self.quorum_block().await?;
+        let address = *address;
+        self.quorum_read("balance", |client| async move {
+            match token {
+                None => Ok(client.get_balance(address, block).await?),
+                Some(token) => {
+                    let erc20 = token::Erc20::new(token, client);
+                    let mut balance_call = erc20.balance_of(address);
+                    if let Some(block) = block {
+                        balance_call = balance_call.block(block);
+                    }
+                    Ok(balance_call.call().await?)
+                }
+            }
+        }).await
+    }
+
+    /// The latest answer of a Chainlink-style aggregator in whole units, failing if it's stale or not positive
+    pub async fn feed_price(&self, feed: Address) -> Result<f64, anyhow::Error> {
+        let aggregator = feed::Aggregator::new(feed, self.client.clone());
+        let decimals = aggregator.decimals().call().await?;
+        let (_, answer, _, updated_at, _) = aggregator.latest_round_data().call().await?;
+
+        if answer <= I256::zero() {
+            bail!("Price feed {:?} has no valid answer", feed)
+        }
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+        if updated_at.as_u64() + MAX_PRICE_FEED_AGE < now {
+            bail!("Price feed {:?} hasn't been updated for {} hours", feed, (now - updated_at.as_u64()) / 3600)
+        }
+
+        let answer = ethers::utils::format_units(answer.into_raw(), decimals as u32)?;
+        Ok(answer.parse()?)
+    }
+
+    /// The current owner of the [GauloiFactory]
+    pub async fn owner(&self) -> Result<Address, anyhow::Error> {
+        let gauloi = GauloiFactory::new(self.gauloi_address, self.client.clone());
+        Ok(gauloi.owner().call().await?)
+    }
+
+    /// The number of swaps created on the [GauloiFactory], which is also the latest swap ID
+    pub async fn swap_count(&self) -> Result<U256, anyhow::Error> {
+        let gauloi = GauloiFactory::new(self.gauloi_address, self.client.clone());
+        Ok(gauloi.swap_id().call().await?)
+    }
+
+    /// Pause or resume swap creation, only callable by the owner
+    pub async fn set_active(
         &self,
         signer: Wallet<SigningKey>,
-        seller: Address,
-        amount: U256,
-        preimage_hash: [u8; 32],
-        timeout: U256,
+        active: bool,
     ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
-        let from = signer.address();
-        let signed_client = self.client.clone().with_signer(signer);
-        let gauloi = GauloiFactory::new(self.gauloi_address, Arc::new(signed_client));
+        let gauloi = self.signed_gauloi(signer).await?;
 
-        let create_call: ContractCall<_, ()> = gauloi
-            .create_swap(seller.clone(), preimage_hash, timeout)
-            .value(amount);
-        let in_flight = create_call.send().await?;
+        let set_active_call: ContractCall<_, ()> = gauloi.set_active(active);
+        set_active_call.call().await.map_err(decode_call_error)?;
+        let in_flight = set_active_call.send().await.map_err(decode_call_error)?;
 
         let receipt = in_flight.confirmations(1).await?;
 
-        Ok(receipt)
+        ensure_succeeded(receipt)
+    }
+
+    /// Hand ownership of the [GauloiFactory] over to another address, only callable by the owner
+    pub async fn transfer_ownership(
+        &self,
+        signer: Wallet<SigningKey>,
+        new_owner: Address,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let gauloi = self.signed_gauloi(signer).await?;
+
+        let transfer_call: ContractCall<_, ()> = gauloi.transfer_ownership(new_owner);
+        transfer_call.call().await.map_err(decode_call_error)?;
+        let in_flight = transfer_call.send().await.map_err(decode_call_error)?;
+
+        let receipt = in_flight.confirmations(1).await?;
+
+        ensure_succeeded(receipt)
+    }
+
+    /// Lock the ETH or tokens an offer buys in its factory, for the offer's seller to claim with the preimage
+    pub async fn commit_eth(
+        &self,
+        sender: EthSender,
+        db: &SwapStorage,
+        offer: &Offer,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let contract = offer.contract();
+        if let SwapContract::Token(token) = contract {
+            return self.commit_token(sender, db, offer, token).await;
+        }
+        let seller = H160::from(offer.seller_eth_address);
+        let amount = U256::from(offer.bought);
+        let preimage_hash = offer.preimage_hash;
+        let timeout = U256::from(offer.lockup_eth);
+
+        let factory = self.factory_address(contract)?;
+        if !self.is_active_for(contract.factory()).await? {
+            return Err(ContractCheckError::Inactive(factory).into());
+        }
+
+        // the relay factory's createSwap is the same as the GauloiFactory one
+        let gauloi = GauloiFactory::new(factory, self.client.clone());
+
+        let create_call: ContractCall<_, ()> = gauloi
+            .create_swap(seller, preimage_hash, timeout)
+            .value(amount)
+            .from(sender.address());
+        create_call.call().await.map_err(decode_call_error)?;
+
+        self.send_as(sender, create_call.tx, EthTxKind::Commit, preimage_hash, db).await
+    }
+
+    /// Lock ERC-20 tokens in the [token::GauloiTokenFactory], approving it to take them first if needed
+    async fn commit_token(
+        &self,
+        sender: EthSender,
+        db: &SwapStorage,
+        offer: &Offer,
+        token: Address,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let seller = H160::from(offer.seller_eth_address);
+        let amount = U256::from(offer.bought);
+        let preimage_hash = offer.preimage_hash;
+        let timeout = U256::from(offer.lockup_eth);
+        let token_gauloi_address = self.token_gauloi_address()?;
+        if !self.is_active_for(Factory::Token).await? {
+            return Err(ContractCheckError::Inactive(token_gauloi_address).into());
+        }
+
+        let from = sender.address();
+        let erc20 = token::Erc20::new(token, self.client.clone());
+
+        let allowance = erc20.allowance(from, token_gauloi_address).call().await?;
+        if allowance < amount {
+            println!("Approving GauloiTokenFactory to lock the tokens...");
+            let approve_call: ContractCall<_, bool> = erc20.approve(token_gauloi_address, amount).from(from);
+            approve_call.call().await.map_err(decode_call_error)?;
+            self.send_as(sender.clone(), approve_call.tx, EthTxKind::Approve, preimage_hash, db).await?;
+        }
+
+        let gauloi = token::GauloiTokenFactory::new(token_gauloi_address, self.client.clone());
+        let create_call: ContractCall<_, ()> = gauloi
+            .create_swap(seller, token, amount, preimage_hash, timeout)
+            .from(from);
+        create_call.call().await.map_err(decode_call_error)?;
+
+        self.send_as(sender, create_call.tx, EthTxKind::Commit, preimage_hash, db).await
     }
 
     pub async fn claim_eth(
+        &self,
+        sender: EthSender,
+        db: &SwapStorage,
+        swap_id: U256,
+        preimage: [u8; 32],
+        contract: SwapContract,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let preimage_hash = sha256::Hash::hash(&preimage).to_byte_array();
+        let from = sender.address();
+
+        let factory = self.factory_address(contract)?;
+
+        let claim_tx = match contract {
+            SwapContract::Token(_) => {
+                let gauloi = token::GauloiTokenFactory::new(factory, self.client.clone());
+                let claim_call: ContractCall<_, ()> = gauloi.claim_swap(swap_id, preimage).from(from);
+                claim_call.call().await.map_err(decode_call_error)?;
+                claim_call.tx
+            }
+            _ => {
+                let gauloi = GauloiFactory::new(factory, self.client.clone());
+                let claim_call: ContractCall<_, ()> = gauloi.claim_swap(swap_id, preimage).from(from);
+                claim_call.call().await.map_err(decode_call_error)?;
+                claim_call.tx
+            }
+        };
+
+        self.send_as(sender, claim_tx, EthTxKind::Claim, preimage_hash, db).await
+    }
+
+    /// Reclaim our committed ETH from a swap that timed out without being claimed
+    pub async fn refund_eth(
+        &self,
+        sender: EthSender,
+        db: &SwapStorage,
+        swap_id: U256,
+        contract: SwapContract,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let swap = self.our_swap_by_id(swap_id, contract).await?;
+        let from = sender.address();
+
+        let factory = self.factory_address(contract)?;
+
+        let refund_tx = match contract {
+            SwapContract::Token(_) => {
+                let gauloi = token::GauloiTokenFactory::new(factory, self.client.clone());
+                let refund_call: ContractCall<_, ()> = gauloi.claim_timeout(swap_id).from(from);
+                refund_call.call().await.map_err(decode_call_error)?;
+                refund_call.tx
+            }
+            _ => {
+                let gauloi = GauloiFactory::new(factory, self.client.clone());
+                let refund_call: ContractCall<_, ()> = gauloi.claim_timeout(swap_id).from(from);
+                refund_call.call().await.map_err(decode_call_error)?;
+                refund_call.tx
+            }
+        };
+
+        self.send_as(sender, refund_tx, EthTxKind::Refund, swap.preimage_hash, db).await
+    }
+
+    /// The EIP-712 digest the BTC seller signs to let `relayer` claim their swap for them, keeping `fee` out of it.
+    /// A zero `relayer` lets anyone submit the claim
+    pub async fn relayed_claim_digest(&self, swap_id: U256, fee: U256, relayer: Address) -> Result<H256, anyhow::Error> {
+        let relay_gauloi_address = self.relay_gauloi_address()?;
+        let chain_id = self.client.get_chainid().await?;
+
+        let domain_separator = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)").to_vec()),
+            Token::FixedBytes(keccak256("GauloiRelayFactory").to_vec()),
+            Token::FixedBytes(keccak256("1").to_vec()),
+            Token::Uint(chain_id),
+            Token::Address(relay_gauloi_address),
+        ]));
+        let struct_hash = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256("RelayedClaim(uint256 swapId,uint256 fee,address relayer)").to_vec()),
+            Token::Uint(swap_id),
+            Token::Uint(fee),
+            Token::Address(relayer),
+        ]));
+
+        let mut digest_input = vec![0x19, 0x01];
+        digest_input.extend_from_slice(&domain_separator);
+        digest_input.extend_from_slice(&struct_hash);
+        Ok(H256::from(keccak256(digest_input)))
+    }
+
+    /// Sign an authorization for a relayer to claim our ETH from a [relay::GauloiRelayFactory] swap
+    pub async fn sign_relayed_claim(
         &self,
         signer: Wallet<SigningKey>,
         swap_id: U256,
         preimage: [u8; 32],
+        fee: U256,
+        relayer: Address,
+    ) -> Result<RelayedClaim, anyhow::Error> {
+        let digest = self.relayed_claim_digest(swap_id, fee, relayer).await?;
+        let signature = signer.sign_hash(digest)?;
+        Ok(RelayedClaim {
+            swap_id,
+            preimage,
+            fee,
+            relayer,
+            signature: signature.to_vec().into(),
+        })
+    }
+
+    /// Submit a BTC seller's [RelayedClaim] as the relayer, checking it pays at least `min_fee` and that the fee
+    /// covers the gas before sending it
+    pub async fn submit_relayed_claim(
+        &self,
+        relayer: Wallet<SigningKey>,
+        db: &SwapStorage,
+        claim: &RelayedClaim,
+        min_fee: U256,
     ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
-        let signed_client = self.client.clone().with_signer(signer);
-        let gauloi = GauloiFactory::new(self.gauloi_address, Arc::new(signed_client));
+        if claim.fee < min_fee {
+            bail!("Relayer fee of {} wei is below the minimum of {} wei", claim.fee, min_fee)
+        }
+        if !claim.relayer.is_zero() && claim.relayer != relayer.address() {
+            bail!("Claim is authorized for relayer {:?}, not us", claim.relayer)
+        }
+        let signature = Signature::try_from(claim.signature.as_ref())?;
 
-        let claim_call: ContractCall<_, ()> = gauloi.claim_swap(swap_id, preimage);
-        let in_flight = claim_call.send().await?;
+        let gauloi = relay::GauloiRelayFactory::new(self.relay_gauloi_address()?, self.client.clone());
+        let claim_call: ContractCall<_, ()> = gauloi
+            .claim_swap_relayed(
+                claim.swap_id,
+                claim.preimage,
+                claim.fee,
+                claim.relayer,
+                signature.v as u8,
+                signature.r.into(),
+                signature.s.into(),
+            )
+            .from(relayer.address());
+        claim_call.call().await.map_err(decode_call_error)?;
 
-        let receipt = in_flight.confirmations(1).await?;
+        let gas = claim_call.estimate_gas().await.map_err(decode_call_error)?;
+        let (max_fee, _) = self.client.estimate_eip1559_fees(None).await?;
+        if gas * max_fee > claim.fee {
+            bail!("Relayer fee of {} wei doesn't cover the {} wei of gas for the claim", claim.fee, gas * max_fee)
+        }
 
-        Ok(receipt)
+        let preimage_hash = sha256::Hash::hash(&claim.preimage).to_byte_array();
+        self.send_as(EthSender::Wallet(relayer), claim_call.tx, EthTxKind::Claim, preimage_hash, db).await
     }
 
-    pub async fn our_swap_id(&self, preimage_hash: [u8; 32]) -> Result<U256, anyhow::Error> {
-        let gauloi = GauloiFactory::new(self.gauloi_address, self.client.clone());
+    /// Send a swap transaction with the wallet, or export it for offline signing and stop with an
+    /// [AwaitingSignatureError] until the signed version is broadcast
+    async fn send_as(
+        &self,
+        sender: EthSender,
+        tx: TypedTransaction,
+        kind: EthTxKind,
+        preimage_hash: [u8; 32],
+        db: &SwapStorage,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        match sender {
+            EthSender::Wallet(signer) => {
+                let signed_client = self.signed_client(signer).await?;
+                self.send_tracked(signed_client, tx, kind, preimage_hash, db).await
+            }
+            EthSender::Offline(from) => Err(self.export_unsigned(from, tx, kind, preimage_hash, db).await?.into()),
+        }
+    }
+
+    /// Fill in the nonce and fees of a transaction and send it, keeping it in storage until it's mined so a stuck
+    /// transaction can be replaced instead of sending a duplicate with a fresh nonce
+    async fn send_tracked(
+        &self,
+        signed_client: Arc<SignedClient>,
+        mut tx: TypedTransaction,
+        kind: EthTxKind,
+        preimage_hash: [u8; 32],
+        db: &SwapStorage,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        signed_client.fill_transaction(&mut tx, None).await?;
+        let nonce = tx.nonce().cloned().unwrap_or_default().as_u64();
+
+        if let Some(private_rpc) = self.private_rpc_for(kind) {
+            let signature = signed_client.signer().sign_transaction(&tx).await?;
+            let raw = tx.rlp_signed(&signature);
+            return self.send_private(private_rpc, raw, &tx, kind, preimage_hash, db).await;
+        }
 
-        let get_id_call: ContractCall<_, U256> = gauloi.hash_to_swap_map(preimage_hash);
-        let swap_id: U256 = get_id_call.call().await?;
-        Ok(swap_id)
+        let in_flight = signed_client.send_transaction(tx.clone(), None).await?;
+        let tx_hash = in_flight.tx_hash();
+        db.add_pending_eth_tx(&PendingEthTx {
+            from: signed_client.address().to_fixed_bytes(),
+            nonce,
+            kind,
+            preimage_hash,
+            tx_hashes: vec![tx_hash.to_fixed_bytes()],
+            tx_json: serde_json::to_string(&tx)?,
+        })?;
+        println!("Sent ETH transaction {:?} with nonce {}", tx_hash, nonce);
+
+        self.await_mined(in_flight, &tx, nonce, db).await
     }
 
-    pub async fn our_swap_by_id(&self, our_swap_id: U256) -> Result<Swap, anyhow::Error> {
-        let gauloi = GauloiFactory::new(self.gauloi_address, self.client.clone());
-        let get_swap_call: ContractCall<_, _> = gauloi.swaps(our_swap_id);
-        let swap_token = get_swap_call.call().await?.into_token();
-        let swap = Swap::from_token(swap_token)?;
+    /// Wait for a tracked transaction to be mined, dropping it from storage once it is and replaying it to find
+    /// out why if it failed
+    async fn await_mined(
+        &self,
+        in_flight: PendingTransaction<'_, EthTransport>,
+        tx: &TypedTransaction,
+        nonce: u64,
+        db: &SwapStorage,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let tx_hash = in_flight.tx_hash();
+        match tokio::time::timeout(TX_CONFIRMATION_TIMEOUT, in_flight.confirmations(1)).await {
+            Ok(receipt) => match receipt? {
+                Some(mined) => Ok(Some(self.check_mined(mined, tx, nonce, db).await?)),
+                None => Ok(None),
+            },
+            Err(_) => bail!(
+                "Transaction {:?} with nonce {} isn't mined yet, run `tx speedup {}` to resend it with a higher fee",
+                tx_hash, nonce, nonce
+            ),
+        }
+    }
+
+    /// Drop a mined transaction from storage, replaying it to find out why if it failed
+    async fn check_mined(
+        &self,
+        mined: TransactionReceipt,
+        tx: &TypedTransaction,
+        nonce: u64,
+        db: &SwapStorage,
+    ) -> Result<TransactionReceipt, anyhow::Error> {
+        db.remove_pending_eth_tx(sender_of(tx), nonce)?;
+        if mined.status == Some(U64::zero()) {
+            let reason = match self.failure_reason(tx, mined.block_number).await {
+                Some(revert) => revert.to_string(),
+                None => "no revert reason available".to_string(),
+            };
+            return Err(FailedTransactionError { tx_hash: mined.transaction_hash, reason }.into());
+        }
+        Ok(mined)
+    }
+
+    /// Send a signed transaction through the private endpoint so a claim's preimage isn't in the public mempool
+    /// before it's mined, falling back to the public mempool if it isn't mined in time
+    async fn send_private(
+        &self,
+        private_rpc: &PrivateRpc,
+        raw: Bytes,
+        tx: &TypedTransaction,
+        kind: EthTxKind,
+        preimage_hash: [u8; 32],
+        db: &SwapStorage,
+    ) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let nonce = tx.nonce().cloned().unwrap_or_default().as_u64();
+        let tx_hash = H256::from(keccak256(&raw));
+        db.add_pending_eth_tx(&PendingEthTx {
+            from: sender_of(tx),
+            nonce,
+            kind,
+            preimage_hash,
+            tx_hashes: vec![tx_hash.to_fixed_bytes()],
+            tx_json: serde_json::to_string(tx)?,
+        })?;
+
+        let max_block = self.client.get_block_number().await? + PRIVATE_TX_MAX_BLOCKS;
+        match private_rpc.send(&raw, max_block).await {
+            Ok(()) => {
+                println!("Sent ETH transaction {:?} with nonce {} privately", tx_hash, nonce);
+                let mined = tokio::time::timeout(private_rpc.fallback, async {
+                    let mut blocks = self.block_waiter().await?;
+                    loop {
+                        if let Some(receipt) = self.client.get_transaction_receipt(tx_hash).await? {
+                            return Ok::<_, anyhow::Error>(receipt);
+                        }
+                        blocks.next().await;
+                    }
+                }).await;
+                if let Ok(mined) = mined {
+                    return Ok(Some(self.check_mined(mined?, tx, nonce, db).await?));
+                }
+                println!(
+                    "{} not mined privately after {}s, sending it to the public mempool",
+                    Paint::yellow("Warning:"), private_rpc.fallback.as_secs()
+                );
+            }
+            Err(e) => println!("{} private submission failed ({}), sending it to the public mempool", Paint::yellow("Warning:"), e),
+        }
+
+        let in_flight = match self.client.send_raw_transaction(raw).await {
+            Ok(in_flight) => in_flight,
+            Err(e) => match self.client.get_transaction_receipt(tx_hash).await? {
+                // it was mined privately in the meantime
+                Some(mined) => return Ok(Some(self.check_mined(mined, tx, nonce, db).await?)),
+                None => return Err(e.into()),
+            },
+        };
+        println!("Sent ETH transaction {:?} with nonce {}", tx_hash, nonce);
+        self.await_mined(in_flight, tx, nonce, db).await
+    }
+
+    /// Fill in the nonce and fees of a transaction for an air-gapped account and print it unsigned, keeping it so
+    /// the signed version can be checked before it's sent by [EthApi::broadcast_signed]
+    async fn export_unsigned(
+        &self,
+        from: Address,
+        mut tx: TypedTransaction,
+        kind: EthTxKind,
+        preimage_hash: [u8; 32],
+        db: &SwapStorage,
+    ) -> Result<AwaitingSignatureError, anyhow::Error> {
+        let chain_id = self.client.get_chainid().await?;
+        tx.set_from(from);
+        tx.set_chain_id(chain_id.as_u64());
+        self.client.fill_transaction(&mut tx, None).await?;
+        let nonce = tx.nonce().cloned().unwrap_or_default().as_u64();
+
+        db.add_unsigned_eth_tx(&PendingEthTx {
+            from: from.to_fixed_bytes(),
+            nonce,
+            kind,
+            preimage_hash,
+            tx_hashes: vec![],
+            tx_json: serde_json::to_string(&tx)?,
+        })?;
+        println!("Unsigned {:?} transaction for {:?} with nonce {}:", kind, from, nonce);
+        println!("{}", serde_json::to_string_pretty(&tx)?);
+        println!("RLP: {}", Paint::cyan(ethers::utils::hex::encode_prefixed(tx.rlp())));
+
+        Ok(AwaitingSignatureError { kind, nonce })
+    }
+
+    /// Check an offline-signed transaction against the one exported for it, then send it and track it until it's
+    /// mined like any other swap transaction
+    pub async fn broadcast_signed(&self, db: &SwapStorage, raw: Bytes) -> Result<Option<TransactionReceipt>, anyhow::Error> {
+        let (mut signed, signature) = TypedTransaction::decode_signed(&rlp::Rlp::new(raw.as_ref()))?;
+        let from = signature.recover(signed.sighash())?;
+        let nonce = signed.nonce().cloned().unwrap_or_default().as_u64();
+
+        let Some(unsigned) = db.get_unsigned_eth_tx(from.to_fixed_bytes(), nonce)? else {
+            bail!("No transaction from {:?} with nonce {} was exported for offline signing", from, nonce)
+        };
+        let expected: TypedTransaction = serde_json::from_str(&unsigned.tx_json)?;
+        if expected.from() != Some(&from) {
+            bail!("Transaction is signed by {:?} instead of {:?}", from, expected.from().cloned().unwrap_or_default())
+        }
+        if signed.to() != expected.to()
+            || signed.value() != expected.value()
+            || signed.data() != expected.data()
+            || signed.chain_id() != expected.chain_id()
+        {
+            bail!("Signed transaction doesn't match the {:?} transaction exported with nonce {}", unsigned.kind, nonce)
+        }
+
+        signed.set_from(from);
+        if let Some(private_rpc) = self.private_rpc_for(unsigned.kind) {
+            db.remove_unsigned_eth_tx(unsigned.from, nonce)?;
+            return self.send_private(private_rpc, raw, &signed, unsigned.kind, unsigned.preimage_hash, db).await;
+        }
+
+        let in_flight = self.client.send_raw_transaction(raw).await?;
+        let tx_hash = in_flight.tx_hash();
+        db.remove_unsigned_eth_tx(unsigned.from, nonce)?;
+        db.add_pending_eth_tx(&PendingEthTx {
+            tx_hashes: vec![tx_hash.to_fixed_bytes()],
+            tx_json: serde_json::to_string(&signed)?,
+            ..unsigned
+        })?;
+        println!("Sent ETH transaction {:?} with nonce {}", tx_hash, nonce);
+
+        self.await_mined(in_flight, &signed, nonce, db).await
+    }
+
+    /// Replay a transaction that was mined but failed on the state before its block to find out why
+    async fn failure_reason(&self, tx: &TypedTransaction, block_number: Option<U64>) -> Option<GauloiRevert> {
+        let parent_block = block_number?.checked_sub(U64::one())?;
+        let err = self.client.call(tx, Some(parent_block.into())).await.err()?;
+        let data = RpcError::as_error_response(&err)?.as_revert_data()?;
+        Some(GauloiRevert::from_revert_data(&data))
+    }
+
+    /// Resend a pending transaction with the same nonce and a higher fee. If `cancel` is set, the replacement is a
+    /// 0-value send to ourselves instead
+    pub async fn replace_pending_tx(
+        &self,
+        signer: Wallet<SigningKey>,
+        db: &SwapStorage,
+        nonce: u64,
+        cancel: bool,
+    ) -> Result<H256, anyhow::Error> {
+        let from = signer.address();
+        let Some(mut pending) = db.get_pending_eth_tx(from.to_fixed_bytes(), nonce)? else {
+            bail!("No pending ETH transaction from {:?} with nonce {}", from, nonce)
+        };
+        let signed_client = self.signed_client(signer).await?;
+
+        let mut tx: TypedTransaction = serde_json::from_str(&pending.tx_json)?;
+        if let Some(signed_by) = tx.from().filter(|signed_by| **signed_by != from) {
+            bail!("Transaction with nonce {} was signed by {:?}, replace it from that account", nonce, signed_by)
+        }
+        if cancel {
+            tx.set_to(from);
+            tx.set_value(U256::zero());
+            tx.set_data(Bytes::default());
+            tx.set_gas(21_000);
+        }
+
+        // replacements need at least a 10% bump, but make sure we're also above the current network fee
+        let (max_fee, priority_fee) = self.client.estimate_eip1559_fees(None).await?;
+        match tx {
+            TypedTransaction::Eip1559(ref mut inner) => {
+                let bumped_max = bump_fee(inner.max_fee_per_gas.unwrap_or_default());
+                let bumped_priority = bump_fee(inner.max_priority_fee_per_gas.unwrap_or_default());
+                inner.max_priority_fee_per_gas = Some(bumped_priority.max(priority_fee));
+                inner.max_fee_per_gas = Some(bumped_max.max(max_fee).max(bumped_priority.max(priority_fee)));
+            }
+            _ => {
+                let gas_price = self.client.get_gas_price().await?;
+                let bumped = bump_fee(tx.gas_price().unwrap_or_default());
+                tx.set_gas_price(bumped.max(gas_price));
+            }
+        }
+
+        let in_flight = signed_client.send_transaction(tx.clone(), None).await?;
+        let tx_hash = in_flight.tx_hash();
+
+        pending.tx_hashes.push(tx_hash.to_fixed_bytes());
+        pending.tx_json = serde_json::to_string(&tx)?;
+        if cancel {
+            pending.kind = EthTxKind::Cancel;
+        }
+        db.add_pending_eth_tx(&pending)?;
+
+        Ok(tx_hash)
+    }
+
+    /// Check every pending transaction against the chain, dropping the ones that have been mined or whose nonce
+    /// has been used by another transaction
+    pub async fn reconcile_pending_txs(
+        &self,
+        db: &SwapStorage,
+        address: Address,
+    ) -> Result<Vec<(PendingEthTx, PendingTxState)>, anyhow::Error> {
+        // offline-signed transactions can come from another account than ours
+        let mut mined_nonces: HashMap<Address, u64> = HashMap::new();
+        let mut reconciled = Vec::new();
+        for pending in db.get_pending_eth_txs()? {
+            let from = match Address::from(pending.from) {
+                from if from.is_zero() => address,
+                from => from,
+            };
+            let mined_nonce = match mined_nonces.get(&from) {
+                Some(mined_nonce) => *mined_nonce,
+                None => {
+                    let mined_nonce = self
+                        .client
+                        .get_transaction_count(from, Some(BlockNumber::Latest.into()))
+                        .await?
+                        .as_u64();
+                    mined_nonces.insert(from, mined_nonce);
+                    mined_nonce
+                }
+            };
+            let mut tx_state = if pending.nonce < mined_nonce {
+                PendingTxState::Replaced
+            } else {
+                PendingTxState::Pending
+            };
+            for tx_hash in pending.tx_hashes.iter().rev() {
+                let tx_hash = H256::from(*tx_hash);
+                if let Some(receipt) = self.client.get_transaction_receipt(tx_hash).await? {
+                    if receipt.block_number.is_some() {
+                        tx_state = PendingTxState::Mined(tx_hash);
+                        break;
+                    }
+                }
+            }
+            if tx_state != PendingTxState::Pending {
+                db.remove_pending_eth_tx(pending.from, pending.nonce)?;
+            }
+            reconciled.push((pending, tx_state));
+        }
+        Ok(reconciled)
+    }
+
+    /// Watch pending transactions for a `claimSwap` of our swap, returning the preimage as soon as it's broadcast
+    /// instead of waiting for it to be mined. Subscribes to pending transactions over WebSocket or IPC, otherwise
+    /// uses a pending transaction filter on the primary RPC, falling back to polling `txpool_content` if the RPC
+    /// doesn't support filters
+    pub async fn watch_pending_preimage(
+        &self,
+        swap_id: U256,
+        preimage_hash: [u8; 32],
+        contract: SwapContract,
+    ) -> Result<[u8; 32], anyhow::Error> {
+        let factory = self.factory_address(contract)?;
+
+        if self.supports_pubsub() {
+            if let Ok(mut pending_txs) = self.client.subscribe_pending_txs().await {
+                while let Some(tx_hash) = pending_txs.next().await {
+                    // a pending transaction can be dropped or the node can hiccup, neither should end the watch
+                    let Ok(Some(tx)) = self.client.get_transaction(tx_hash).await else {
+                        continue;
+                    };
+                    if let Some(preimage) = claimed_preimage(&tx, factory, swap_id, preimage_hash) {
+                        return Ok(preimage);
+                    }
+                }
+            }
+        }
+
+        if let Ok(mut pending_txs) = self.client.watch_pending_transactions().await {
+            while let Some(tx_hash) = pending_txs.next().await {
+                let Ok(Some(tx)) = self.client.get_transaction(tx_hash).await else {
+                    continue;
+                };
+                if let Some(preimage) = claimed_preimage(&tx, factory, swap_id, preimage_hash) {
+                    return Ok(preimage);
+                }
+            }
+        }
+
+        loop {
+            let txpool = self.client.txpool_content().await?;
+            for tx in txpool.pending.values().flat_map(|txs| txs.values()) {
+                if let Some(preimage) = claimed_preimage(tx, factory, swap_id, preimage_hash) {
+                    return Ok(preimage);
+                }
+            }
+            tokio::time::sleep(TXPOOL_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Walk every swap in the configured factories and return the ones that name `involving` as the committer or
+    /// the recipient, for rebuilding our swaps from the chain
+    pub async fn scan_swaps(&self, involving: Address) -> Result<Vec<ScannedSwap>, anyhow::Error> {
+        let mut found = Vec::new();
+
+        // the relay factory has the same swaps layout as the GauloiFactory
+        let mut factories = vec![(SwapContract::Gauloi, self.gauloi_address)];
+        if let Some(relay_gauloi_address) = self.relay_gauloi_address {
+            factories.push((SwapContract::Relay, relay_gauloi_address));
+        }
+        for (contract, factory) in factories {
+            let gauloi = GauloiFactory::new(factory, self.client.clone());
+            let count = gauloi.swap_id().call().await?.as_u64();
+            let calls = (1..=count).map(|i| gauloi.swaps(U256::from(i))).collect();
+            for (i, token) in self.batch_call(calls).await?.into_iter().enumerate() {
+                let swap: LockedSwap = Swap::from_token(token)?.into();
+                if swap.buyer == involving || swap.seller == involving {
+                    found.push(ScannedSwap { contract, swap_id: U256::from(i + 1), swap });
+                }
+            }
+        }
+
+        if let Some(token_gauloi_address) = self.token_gauloi_address {
+            let gauloi = token::GauloiTokenFactory::new(token_gauloi_address, self.client.clone());
+            let count = gauloi.swap_id().call().await?.as_u64();
+            let calls = (1..=count).map(|i| gauloi.get_swap(U256::from(i))).collect();
+            for (i, token) in self.batch_call(calls).await?.into_iter().enumerate() {
+                let token_swap = token::TokenSwap::from_token(token)?;
+                if token_swap.buyer == involving || token_swap.seller == involving {
+                    found.push(ScannedSwap {
+                        contract: SwapContract::Token(token_swap.token),
+                        swap_id: U256::from(i + 1),
+                        swap: token_swap.into(),
+                    });
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Make read calls through Multicall3 in batches, falling back to one at a time on chains where it isn't
+    /// deployed
+    async fn batch_call<D: Detokenize>(
+        &self,
+        calls: Vec<ContractCall<Provider<EthTransport>, D>>,
+    ) -> Result<Vec<Token>, anyhow::Error> {
+        let mut multicall = Multicall::new(self.client.clone(), None).await.ok();
+        let mut results = Vec::with_capacity(calls.len());
+
+        for batch in calls.chunks(MULTICALL_BATCH_SIZE) {
+            if let Some(batch_call) = multicall.as_mut() {
+                batch_call.clear_calls();
+                for call in batch {
+                    batch_call.add_call(call.clone(), false);
+                }
+                match batch_call.call_raw().await {
+                    Ok(outputs) => {
+                        for output in outputs {
+                            results.push(output.map_err(|data| anyhow::anyhow!("read reverted with 0x{}", hex::encode(data)))?);
+                        }
+                        continue;
+                    }
+                    Err(_) => multicall = None,
+                }
+            }
+
+            for call in batch {
+                let data = self.client.call(&call.tx, call.block).await?;
+                let mut outputs = call.function.decode_output(&data)?;
+                results.push(if outputs.len() == 1 { outputs.remove(0) } else { Token::Tuple(outputs) });
+            }
+        }
+        Ok(results)
+    }
+
+    pub async fn our_swap_id(&self, preimage_hash: [u8; 32], contract: SwapContract) -> Result<U256, anyhow::Error> {
+        let block = self.quorum_block().await?;
+        let factory = self.factory_address(contract)?;
+
+        self.quorum_read("swap ID", |client| async move {
+            let mut get_id_call: ContractCall<_, U256> = match contract {
+                SwapContract::Token(_) => token::GauloiTokenFactory::new(factory, client).hash_to_swap_map(preimage_hash),
+                _ => GauloiFactory::new(factory, client).hash_to_swap_map(preimage_hash),
+            };
+            if let Some(block) = block {
+                get_id_call = get_id_call.block(block);
+            }
+            let swap_id: U256 = get_id_call.call().await?;
+            Ok(swap_id)
+        }).await
+    }
+
+    /// Get a swap by ID from whichever factory the contract is
+    pub async fn our_swap_by_id(&self, our_swap_id: U256, contract: SwapContract) -> Result<LockedSwap, anyhow::Error> {
+        let block = self.quorum_block().await?;
+        let factory = self.factory_address(contract)?;
+
+        self.quorum_read("swap state", |client| async move {
+            if let SwapContract::Token(_) = contract {
+                let gauloi = token::GauloiTokenFactory::new(factory, client);
+                let mut get_swap_call = gauloi.get_swap(our_swap_id);
+                if let Some(block) = block {
+                    get_swap_call = get_swap_call.block(block);
+                }
+                let token_swap: token::TokenSwap = get_swap_call.call().await?;
+                return Ok(token_swap.into());
+            }
+
+            let gauloi = GauloiFactory::new(factory, client);
+            let mut get_swap_call: ContractCall<_, _> = gauloi.swaps(our_swap_id);
+            if let Some(block) = block {
+                get_swap_call = get_swap_call.block(block);
+            }
+            let swap_token = get_swap_call.call().await?.into_token();
+            let swap = Swap::from_token(swap_token)?;
+
+            Ok(swap.into())
+        }).await
+    }
+
+    pub async fn our_swap(&self, preimage_hash: [u8; 32], contract: SwapContract) -> Result<LockedSwap, anyhow::Error> {
+        let swap_id = self.our_swap_id(preimage_hash, contract).await?;
+        return self.our_swap_by_id(swap_id, contract).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::AbiEncode;
+    use ethers::providers::MockProvider;
+    use ethers::utils::hex;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::offer::SAMPLE_PREIMAGE;
+
+    const TOKEN: Address = H160([9; 20]);
+    const TOKEN_GAULOI: Address = H160([10; 20]);
+    const RELAY_GAULOI: Address = H160([11; 20]);
+
+    /// An [EthApi] whose single endpoint answers with whatever is queued on the returned mock
+    fn mocked() -> (EthApi, MockProvider) {
+        let mock = MockProvider::new();
+        let client = Arc::new(Provider::new(EthTransport::Mock(mock.clone())).interval(Duration::from_millis(10)));
+        let api = EthApi {
+            client: client.clone(),
+            read_clients: vec![("mock".to_string(), client)],
+            quorum: 1,
+            gauloi_address: H160([8; 20]),
+            token_gauloi_address: Some(TOKEN_GAULOI),
+            relay_gauloi_address: Some(RELAY_GAULOI),
+            private_rpc: None,
+        };
+        (api, mock)
+    }
+
+    /// Queue the responses to the next requests in the order they're made, the mock hands out the last pushed first
+    fn respond(mock: &MockProvider, responses: Vec<Value>) {
+        for response in responses.into_iter().rev() {
+            mock.push::<Value, _>(response).unwrap();
+        }
+    }
+
+    /// The `eth_call` response returning `tokens`
+    fn returns(tokens: &[Token]) -> Value {
+        json!(hex::encode_prefixed(abi::encode(tokens)))
+    }
+
+    /// Responses to estimating EIP-1559 fees: the latest block for its base fee, then the fee history
+    fn fee_responses() -> Vec<Value> {
+        vec![
+            json!({ "number": "0x10", "baseFeePerGas": "0x3b9aca00" }),
+            json!({ "baseFeePerGas": ["0x3b9aca00"], "gasUsedRatio": [0.5], "oldestBlock": "0x10", "reward": [["0x3b9aca00"]] }),
+        ]
+    }
+
+    /// Responses to exporting an unsigned transaction: the chain id, the fees, then the gas estimate
+    fn export_responses() -> Vec<Value> {
+        let mut responses = vec![json!("0xaa36a7")];
+        responses.extend(fee_responses());
+        responses.push(json!("0x5208"));
+        responses
+    }
+
+    fn token_offer() -> Offer {
+        Offer {
+            token_address: Some(TOKEN.to_fixed_bytes()),
+            token_decimals: 6,
+            bought: 50_000_000_000,
+            ..Offer::sample()
+        }
+    }
+
+    fn exported(db: &SwapStorage, from: Address) -> (PendingEthTx, TypedTransaction) {
+        let unsigned = db.get_unsigned_eth_tx(from.to_fixed_bytes(), 0).unwrap().expect("no transaction was exported");
+        let tx = serde_json::from_str(&unsigned.tx_json).unwrap();
+        (unsigned, tx)
+    }
+
+    fn awaited_kind(result: Result<Option<TransactionReceipt>, anyhow::Error>) -> EthTxKind {
+        result.unwrap_err().downcast::<AwaitingSignatureError>().expect("should wait for a signature").kind
+    }
+
+    #[tokio::test]
+    async fn token_commit_approves_the_factory_first() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("token-approve");
+        let offer = token_offer();
+        let from = H160(offer.buyer_eth_address);
+
+        let mut responses = vec![
+            returns(&[Token::Bool(true)]),
+            returns(&[Token::Uint(U256::zero())]),
+            returns(&[Token::Bool(true)]),
+        ];
+        responses.extend(export_responses());
+        respond(&mock, responses);
+
+        let result = api.commit_eth(EthSender::Offline(from), &db, &offer).await;
+        assert_eq!(awaited_kind(result), EthTxKind::Approve);
+
+        let (unsigned, tx) = exported(&db, from);
+        assert_eq!(unsigned.kind, EthTxKind::Approve);
+        assert_eq!(unsigned.preimage_hash, offer.preimage_hash);
+        assert_eq!(tx.to_addr(), Some(&TOKEN));
+        let approve = token::ApproveCall { spender: TOKEN_GAULOI, amount: U256::from(offer.bought) };
+        assert_eq!(tx.data(), Some(&approve.encode().into()));
+    }
+
+    #[tokio::test]
+    async fn token_commit_locks_the_tokens() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("token-commit");
+        let offer = token_offer();
+        let from = H160(offer.buyer_eth_address);
+        let amount = U256::from(offer.bought);
+
+        let mut responses = vec![
+            returns(&[Token::Bool(true)]),
+            returns(&[Token::Uint(amount)]),
+            json!("0x"),
+        ];
+        responses.extend(export_responses());
+        respond(&mock, responses);
+
+        let result = api.commit_eth(EthSender::Offline(from), &db, &offer).await;
+        assert_eq!(awaited_kind(result), EthTxKind::Commit);
+
+        let (_, tx) = exported(&db, from);
+        assert_eq!(tx.to_addr(), Some(&TOKEN_GAULOI));
+        assert_eq!(tx.value().cloned().unwrap_or_default(), U256::zero());
+        let create = token::CreateSwapCall {
+            buyer: H160(offer.seller_eth_address),
+            token: TOKEN,
+            amount,
+            preimage_hash: offer.preimage_hash,
+            unlock_blocks: U256::from(offer.lockup_eth),
+        };
+        assert_eq!(tx.data(), Some(&create.encode().into()));
+    }
+
+    #[tokio::test]
+    async fn token_commit_refuses_an_inactive_factory() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("token-inactive");
+        let offer = token_offer();
+        respond(&mock, vec![returns(&[Token::Bool(false)])]);
+
+        let result = api.commit_eth(EthSender::Offline(H160(offer.buyer_eth_address)), &db, &offer).await;
+        assert!(matches!(
+            result.unwrap_err().downcast::<ContractCheckError>(),
+            Ok(ContractCheckError::Inactive(address)) if address == TOKEN_GAULOI
+        ));
+    }
+
+    #[tokio::test]
+    async fn token_claim_reveals_the_preimage_to_the_token_factory() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("token-claim");
+        let from = H160([3; 20]);
+        let swap_id = U256::from(42);
+
+        let mut responses = vec![json!("0x")];
+        responses.extend(export_responses());
+        respond(&mock, responses);
+
+        let result = api.claim_eth(EthSender::Offline(from), &db, swap_id, SAMPLE_PREIMAGE, SwapContract::Token(TOKEN)).await;
+        assert_eq!(awaited_kind(result), EthTxKind::Claim);
+
+        let (unsigned, tx) = exported(&db, from);
+        assert_eq!(unsigned.preimage_hash, sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array());
+        assert_eq!(tx.to_addr(), Some(&TOKEN_GAULOI));
+        let claim = token::ClaimSwapCall { swap_id, preimage: SAMPLE_PREIMAGE };
+        assert_eq!(tx.data(), Some(&claim.encode().into()));
+    }
+
+    fn pending_claim(to: Address, input: Vec<u8>) -> Transaction {
+        Transaction { to: Some(to), input: input.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn preimage_is_read_from_a_pending_claim() {
+        let gauloi = H160([8; 20]);
+        let preimage_hash = sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array();
+        let claim = ClaimSwapCall { swap_id: U256::from(42), preimage: SAMPLE_PREIMAGE }.encode();
+
+        let tx = pending_claim(gauloi, claim.clone());
+        assert_eq!(claimed_preimage(&tx, gauloi, U256::from(42), preimage_hash), Some(SAMPLE_PREIMAGE));
+        // another swap's claim, or a claim on another contract, doesn't give us the preimage
+        assert_eq!(claimed_preimage(&tx, gauloi, U256::from(43), preimage_hash), None);
+        assert_eq!(claimed_preimage(&pending_claim(RELAY_GAULOI, claim), gauloi, U256::from(42), preimage_hash), None);
+    }
+
+    #[test]
+    fn preimage_is_read_from_a_pending_relayed_claim() {
+        let preimage_hash = sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array();
+        let claim = relay::ClaimSwapRelayedCall {
+            swap_id: U256::from(42),
+            preimage: SAMPLE_PREIMAGE,
+            fee: U256::from(1000),
+            relayer: Address::zero(),
+            v: 27,
+            r: [1; 32],
+            s: [2; 32],
+        };
+
+        let tx = pending_claim(RELAY_GAULOI, claim.encode());
+        assert_eq!(claimed_preimage(&tx, RELAY_GAULOI, U256::from(42), preimage_hash), Some(SAMPLE_PREIMAGE));
+    }
+
+    #[test]
+    fn preimage_not_matching_the_hash_is_ignored() {
+        let gauloi = H160([8; 20]);
+        let preimage_hash = sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array();
+
+        let wrong_preimage = ClaimSwapCall { swap_id: U256::from(42), preimage: [8; 32] }.encode();
+        assert_eq!(claimed_preimage(&pending_claim(gauloi, wrong_preimage), gauloi, U256::from(42), preimage_hash), None);
+        let refund = ClaimTimeoutCall { swap_id: U256::from(42) }.encode();
+        assert_eq!(claimed_preimage(&pending_claim(gauloi, refund), gauloi, U256::from(42), preimage_hash), None);
+    }
+
+    fn claim_signer() -> LocalWallet {
+        LocalWallet::from_bytes(&[1; 32]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn relayed_claim_is_signed_over_the_eip712_digest() {
+        let (api, mock) = mocked();
+        let relayer = H160([12; 20]);
+        let signer = claim_signer();
+        respond(&mock, vec![json!("0xaa36a7")]);
+
+        let claim = api.sign_relayed_claim(signer.clone(), U256::from(42), SAMPLE_PREIMAGE, U256::from(1000), relayer).await.unwrap();
+
+        // encode the same message through ethers' own EIP-712 implementation to check ours against
+        let typed_data: ethers::types::transaction::eip712::TypedData = serde_json::from_value(json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "RelayedClaim": [
+                    { "name": "swapId", "type": "uint256" },
+                    { "name": "fee", "type": "uint256" },
+                    { "name": "relayer", "type": "address" },
+                ],
+            },
+            "primaryType": "RelayedClaim",
+            "domain": {
+                "name": "GauloiRelayFactory",
+                "version": "1",
+                "chainId": 11155111,
+                "verifyingContract": RELAY_GAULOI,
+            },
+            "message": { "swapId": 42, "fee": 1000, "relayer": relayer },
+        })).unwrap();
+        let digest = H256::from(ethers::types::transaction::eip712::Eip712::encode_eip712(&typed_data).unwrap());
+
+        let signature = Signature::try_from(claim.signature.as_ref()).unwrap();
+        assert_eq!(signature.recover(digest).unwrap(), signer.address());
+        assert_eq!((claim.swap_id, claim.preimage, claim.fee, claim.relayer), (U256::from(42), SAMPLE_PREIMAGE, U256::from(1000), relayer));
+    }
+
+    #[tokio::test]
+    async fn relayer_refuses_claims_below_its_fee_or_for_another_relayer() {
+        let (api, _mock) = mocked();
+        let db = SwapStorage::temporary("relayer-refuses");
+        let relayer = claim_signer();
+        let claim = RelayedClaim {
+            swap_id: U256::from(42),
+            preimage: SAMPLE_PREIMAGE,
+            fee: U256::from(1000),
+            relayer: relayer.address(),
+            signature: Bytes::from(vec![0; 65]),
+        };
+
+        let below_fee = api.submit_relayed_claim(relayer.clone(), &db, &claim, U256::from(1001)).await;
+        assert!(below_fee.unwrap_err().to_string().contains("below the minimum"));
+
+        let for_another = RelayedClaim { relayer: H160([12; 20]), ..claim };
+        let wrong_relayer = api.submit_relayed_claim(relayer, &db, &for_another, U256::from(1000)).await;
+        assert!(wrong_relayer.unwrap_err().to_string().contains("not us"));
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    /// Responses to reading a price feed: its decimals, then its latest round
+    fn feed_responses(answer: i64, updated_at: u64) -> Vec<Value> {
+        vec![
+            returns(&[Token::Uint(U256::from(8))]),
+            returns(&[
+                Token::Uint(U256::from(1)),
+                Token::Int(I256::from(answer).into_raw()),
+                Token::Uint(U256::from(updated_at)),
+                Token::Uint(U256::from(updated_at)),
+                Token::Uint(U256::from(1)),
+            ]),
+        ]
+    }
+
+    #[tokio::test]
+    async fn feed_price_is_scaled_by_the_feed_decimals() {
+        let (api, mock) = mocked();
+        respond(&mock, feed_responses(1_550_000_000, now() - 60));
+        assert_eq!(api.feed_price(H160([13; 20])).await.unwrap(), 15.5);
+    }
+
+    #[tokio::test]
+    async fn stale_feed_price_is_refused() {
+        let (api, mock) = mocked();
+        respond(&mock, feed_responses(1_550_000_000, now() - MAX_PRICE_FEED_AGE - 60));
+        assert!(api.feed_price(H160([13; 20])).await.unwrap_err().to_string().contains("hasn't been updated"));
+    }
+
+    #[tokio::test]
+    async fn non_positive_feed_price_is_refused() {
+        let (api, mock) = mocked();
+        respond(&mock, feed_responses(0, now()));
+        assert!(api.feed_price(H160([13; 20])).await.unwrap_err().to_string().contains("no valid answer"));
+    }
+
+    /// A private relay on a local port answering every request with `response`, the requests' bodies and signature
+    /// headers are collected in the returned list
+    async fn mock_relay(response: Value) -> (String, Arc<std::sync::Mutex<Vec<(Value, String)>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(std::sync::Mutex::new(vec![]));
+        let requests = received.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut chunk = [0; 4096];
+                let (headers, body) = loop {
+                    let read = stream.read(&mut chunk).await.unwrap();
+                    request.extend_from_slice(&chunk[..read]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                        let headers = headers.to_lowercase();
+                        let length: usize = headers.lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .map_or(0, |length| length.trim().parse().unwrap());
+                        if body.len() >= length || read == 0 {
+                            break (headers, body.to_string());
+                        }
+                    }
+                };
+                let signature = headers.lines()
+                    .find_map(|line| line.strip_prefix("x-flashbots-signature:"))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                requests.lock().unwrap().push((serde_json::from_str(&body).unwrap(), signature));
+
+                let response = response.to_string();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(), response
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn with_private_relay(api: &mut EthApi, url: String) {
+        api.private_rpc = Some(PrivateRpc {
+            url,
+            fallback: Duration::from_secs(5),
+            auth: LocalWallet::from_bytes(&[2; 32]).unwrap(),
+        });
+    }
+
+    /// Responses to a wallet sending a claim on the [GauloiFactory]: the call, the chain id, then the nonce, fees
+    /// and gas the transaction is filled in with
+    fn wallet_claim_responses() -> Vec<Value> {
+        let mut responses = vec![json!("0x"), json!("0xaa36a7"), json!("0x5")];
+        responses.extend(fee_responses());
+        responses.push(json!("0x5208"));
+        responses
+    }
+
+    fn mined(tx_hash: H256) -> (Value, Value) {
+        let tx = Transaction { hash: tx_hash, block_number: Some(U64::from(17)), ..Default::default() };
+        let receipt = TransactionReceipt {
+            transaction_hash: tx_hash,
+            block_number: Some(U64::from(17)),
+            status: Some(U64::one()),
+            ..Default::default()
+        };
+        (serde_json::to_value(tx).unwrap(), serde_json::to_value(receipt).unwrap())
+    }
+
+    #[tokio::test]
+    async fn claim_is_sent_through_the_private_relay() {
+        let (mut api, mock) = mocked();
+        let db = SwapStorage::temporary("private-claim");
+        let (url, received) = mock_relay(json!({ "jsonrpc": "2.0", "id": 1, "result": "0x01" })).await;
+        with_private_relay(&mut api, url);
+        let signer = claim_signer();
+        let tx_hash = H256::repeat_byte(14);
+
+        let mut responses = wallet_claim_responses();
+        responses.push(json!("0x10"));
+        responses.push(mined(tx_hash).1);
+        respond(&mock, responses);
+
+        let receipt = api.claim_eth(EthSender::Wallet(signer.clone()), &db, U256::from(42), SAMPLE_PREIMAGE, SwapContract::Gauloi).await.unwrap();
+        assert_eq!(receipt.unwrap().transaction_hash, tx_hash);
+        assert!(db.get_pending_eth_txs().unwrap().is_empty());
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (request, signature) = &received[0];
+        assert_eq!(request["method"], "eth_sendPrivateTransaction");
+        assert_eq!(request["params"][0]["maxBlockNumber"], json!(U64::from(0x10 + PRIVATE_TX_MAX_BLOCKS)));
+        assert!(signature.starts_with(&format!("{:?}:0x", LocalWallet::from_bytes(&[2; 32]).unwrap().address())));
+
+        let raw: Bytes = serde_json::from_value(request["params"][0]["tx"].clone()).unwrap();
+        let (tx, tx_signature) = TypedTransaction::decode_signed(&rlp::Rlp::new(raw.as_ref())).unwrap();
+        assert_eq!(tx_signature.recover(tx.sighash()).unwrap(), signer.address());
+        assert_eq!(tx.to_addr(), Some(&H160([8; 20])));
+        assert_eq!(tx.nonce(), Some(&U256::from(5)));
+        let claim = ClaimSwapCall { swap_id: U256::from(42), preimage: SAMPLE_PREIMAGE };
+        assert_eq!(tx.data(), Some(&claim.encode().into()));
+    }
+
+    #[tokio::test]
+    async fn claim_falls_back_to_the_public_mempool_when_the_relay_refuses_it() {
+        let (mut api, mock) = mocked();
+        let db = SwapStorage::temporary("private-fallback");
+        let (url, received) = mock_relay(json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "rejected" } })).await;
+        with_private_relay(&mut api, url);
+        let tx_hash = H256::repeat_byte(15);
+        let (tx, receipt) = mined(tx_hash);
+
+        let mut responses = wallet_claim_responses();
+        responses.extend([json!("0x10"), json!(tx_hash), tx, receipt]);
+        respond(&mock, responses);
+
+        let receipt = api.claim_eth(EthSender::Wallet(claim_signer()), &db, U256::from(42), SAMPLE_PREIMAGE, SwapContract::Gauloi).await.unwrap();
+        assert_eq!(receipt.unwrap().transaction_hash, tx_hash);
+        assert_eq!(received.lock().unwrap().len(), 1);
+        assert!(db.get_pending_eth_txs().unwrap().is_empty());
+    }
+
+    const GWEI: u64 = 1_000_000_000;
+
+    /// Store a claim from the test signer as pending with nonce 5, paying 100 gwei with a 10 gwei tip
+    fn stuck_claim(db: &SwapStorage) -> (PendingEthTx, TypedTransaction) {
+        let from = claim_signer().address();
+        let claim = ClaimSwapCall { swap_id: U256::from(42), preimage: SAMPLE_PREIMAGE };
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(from)
+            .to(H160([8; 20]))
+            .data(claim.encode())
+            .nonce(5)
+            .gas(100_000)
+            .max_fee_per_gas(100 * GWEI)
+            .max_priority_fee_per_gas(10 * GWEI)
+            .chain_id(11155111)
+            .into();
+        let pending = PendingEthTx {
+            from: from.to_fixed_bytes(),
+            nonce: 5,
+            kind: EthTxKind::Claim,
+            preimage_hash: sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array(),
+            tx_hashes: vec![[16; 32]],
+            tx_json: serde_json::to_string(&tx).unwrap(),
+        };
+        db.add_pending_eth_tx(&pending).unwrap();
+        (pending, tx)
+    }
+
+    /// Responses to replacing a transaction: the chain id, the current fees, then the replacement's hash
+    fn replace_responses(tx_hash: H256) -> Vec<Value> {
+        let mut responses = vec![json!("0xaa36a7")];
+        responses.extend(fee_responses());
+        responses.push(json!(tx_hash));
+        responses
+    }
+
+    fn fees(tx: &TypedTransaction) -> (U256, U256) {
+        match tx {
+            TypedTransaction::Eip1559(inner) => (inner.max_fee_per_gas.unwrap(), inner.max_priority_fee_per_gas.unwrap()),
+            _ => panic!("not an EIP-1559 transaction"),
+        }
+    }
+
+    #[tokio::test]
+    async fn speedup_resends_the_transaction_with_the_same_nonce_and_higher_fees() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("speedup");
+        let (pending, stuck) = stuck_claim(&db);
+        let replacement_hash = H256::repeat_byte(17);
+        respond(&mock, replace_responses(replacement_hash));
+
+        let tx_hash = api.replace_pending_tx(claim_signer(), &db, 5, false).await.unwrap();
+        assert_eq!(tx_hash, replacement_hash);
+
+        let replaced = db.get_pending_eth_tx(pending.from, 5).unwrap().unwrap();
+        assert_eq!(replaced.kind, EthTxKind::Claim);
+        assert_eq!(replaced.tx_hashes, vec![[16; 32], replacement_hash.to_fixed_bytes()]);
+        let replacement: TypedTransaction = serde_json::from_str(&replaced.tx_json).unwrap();
+        assert_eq!(replacement.nonce(), Some(&U256::from(5)));
+        assert_eq!((replacement.to(), replacement.data(), replacement.gas()), (stuck.to(), stuck.data(), stuck.gas()));
+        assert_eq!(fees(&replacement), (bump_fee(U256::from(100 * GWEI)), bump_fee(U256::from(10 * GWEI))));
+    }
+
+    #[tokio::test]
+    async fn cancel_replaces_the_transaction_with_an_empty_send_to_ourselves() {
+        let (api, mock) = mocked();
+        let db = SwapStorage::temporary("cancel");
+        let (pending, _) = stuck_claim(&db);
+        respond(&mock, replace_responses(H256::repeat_byte(17)));
+
+        api.replace_pending_tx(claim_signer(), &db, 5, true).await.unwrap();
 
-        Ok(swap)
+        let replaced = db.get_pending_eth_tx(pending.from, 5).unwrap().unwrap();
+        assert_eq!(replaced.kind, EthTxKind::Cancel);
+        assert_eq!(replaced.tx_hashes.len(), 2);
+        let replacement: TypedTransaction = serde_json::from_str(&replaced.tx_json).unwrap();
+        assert_eq!(replacement.nonce(), Some(&U256::from(5)));
+        assert_eq!(replacement.to_addr(), Some(&claim_signer().address()));
+        assert_eq!(replacement.value(), Some(&U256::zero()));
+        assert_eq!(replacement.data().cloned().unwrap_or_default(), Bytes::default());
+        assert_eq!(replacement.gas(), Some(&U256::from(21_000)));
+        assert_eq!(fees(&replacement), (bump_fee(U256::from(100 * GWEI)), bump_fee(U256::from(10 * GWEI))));
     }
 
-    pub async fn our_swap(&self, preimage_hash: [u8; 32]) -> Result<Swap, anyhow::Error> {
-        let swap_id = self.our_swap_id(preimage_hash).await?;
-        return self.our_swap_by_id(swap_id).await;
+    #[tokio::test]
+    async fn replacing_a_nonce_with_nothing_pending_fails() {
+        let (api, _mock) = mocked();
+        let db = SwapStorage::temporary("replace-unknown");
+        let result = api.replace_pending_tx(claim_signer(), &db, 5, false).await;
+        assert!(result.unwrap_err().to_string().contains("No pending ETH transaction"));
     }
 }
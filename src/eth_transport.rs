@@ -0,0 +1,83 @@
+//! JSON-RPC transport for the EVM leg, picked from the endpoint in the chain config
+
+use std::fmt::Debug;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use ethers::providers::MockProvider;
+use ethers::providers::{Http, Ipc, JsonRpcClient, ProviderError, PubsubClient, Ws};
+use ethers::types::U256;
+use futures::Stream;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
+
+/// HTTP, WebSocket or IPC connection to an EVM node, only WebSocket and IPC support subscriptions
+#[derive(Debug, Clone)]
+pub enum EthTransport {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+    /// Canned responses, for tests
+    #[cfg(test)]
+    Mock(MockProvider),
+}
+
+impl EthTransport {
+    /// Connect to an endpoint, `http(s)://` and `ws(s)://` URLs select those transports and anything else is
+    /// treated as the path of an IPC socket
+    pub async fn connect(endpoint: &str) -> Result<Self, anyhow::Error> {
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            Ok(EthTransport::Http(endpoint.parse()?))
+        } else if endpoint.starts_with("ws://") || endpoint.starts_with("wss://") {
+            Ok(EthTransport::Ws(Ws::connect(endpoint).await?))
+        } else {
+            let path = endpoint.strip_prefix("ipc://").unwrap_or(endpoint);
+            Ok(EthTransport::Ipc(Ipc::connect(path).await?))
+        }
+    }
+
+    /// Whether new blocks and pending transactions can be subscribed to instead of polled
+    pub fn supports_pubsub(&self) -> bool {
+        matches!(self, EthTransport::Ws(_) | EthTransport::Ipc(_))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for EthTransport {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            EthTransport::Http(http) => Ok(JsonRpcClient::request(http, method, params).await?),
+            EthTransport::Ws(ws) => Ok(JsonRpcClient::request(ws, method, params).await?),
+            EthTransport::Ipc(ipc) => Ok(JsonRpcClient::request(ipc, method, params).await?),
+            #[cfg(test)]
+            EthTransport::Mock(mock) => Ok(JsonRpcClient::request(mock, method, params).await?),
+        }
+    }
+}
+
+impl PubsubClient for EthTransport {
+    type NotificationStream = Pin<Box<dyn Stream<Item = Box<RawValue>> + Send>>;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            EthTransport::Ws(ws) => Ok(Box::pin(ws.subscribe(id)?)),
+            EthTransport::Ipc(ipc) => Ok(Box::pin(ipc.subscribe(id)?)),
+            _ => Err(ProviderError::UnsupportedRPC),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            EthTransport::Ws(ws) => Ok(ws.unsubscribe(id)?),
+            EthTransport::Ipc(ipc) => Ok(ipc.unsubscribe(id)?),
+            _ => Err(ProviderError::UnsupportedRPC),
+        }
+    }
+}
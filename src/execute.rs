@@ -1,33 +1,71 @@
 use std::time::Duration;
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use clap::Parser;
-use ethers::types::{H160, U256};
+use ethers::types::{Address, U256};
+use ethers::utils::parse_ether;
 use tokio::time::sleep;
 
-use crate::{offer::Offer, state::GauloiState};
+use crate::{offer::{EthTxKind, Offer}, state::GauloiState};
+use crate::eth_api::{LockedSwap, SwapContract};
+use crate::relayer::{RelayerInfo, RelayerResponse};
+use crate::timelock;
+
+/// Largest share of the swap, in percent, we let a relayer keep for submitting the claim
+const MAX_RELAYER_FEE_PERCENT: u64 = 5;
 
 /// Execute a swap by id
 #[derive(Parser, Debug)]
 pub struct ExecuteArgs {
     swap_id: usize,
+
+    /// Watch pending ETH transactions to learn the preimage before the claim is mined
+    #[clap(short, long)]
+    watch_mempool: bool,
+
+    /// Print our ETH transactions unsigned for the offline signer instead of sending them, see `eth-broadcast`
+    #[clap(long)]
+    offline: bool,
+
+    /// URL of a relayer (see `relay`) to claim relayed swaps through, so we don't need ETH for gas
+    #[clap(long)]
+    relayer: Option<String>,
+
+    /// ETH the relayer keeps out of the swap for submitting the claim, at most 5% of the swap
+    #[clap(long, default_value = "0.001")]
+    relayer_fee: String,
 }
 
 pub async fn execute(state: &mut GauloiState, args: ExecuteArgs) -> Result<()> {
     let offer = state.db.get_complete_offer(args.swap_id)?;
+    if offer.recovered {
+        bail!("Only the ETH side of this swap was recovered by `scan-eth`, it can't be executed, use `refund` to reclaim ETH we committed to it")
+    }
+
+    // refuse to trade against a look-alike contract, a paused one only stops us committing to a new swap below
+    state.eth_api.ensure_genuine_for(offer.contract()).await?;
+    if args.relayer.is_some() {
+        // catch a mistyped fee before we lock anything up
+        relayer_fee(&offer, &args)?;
+    }
 
     println!("=== Found offer, executing swap... ===");
     println!("[1/4] Checking BTC Commit...");
     await_or_start_btc_commitment(state, &offer).await?;
     println!("[2/4] Checking ETH Commit...");
-    await_or_start_eth_commitment(state, &offer).await?;
+    await_or_start_eth_commitment(state, &offer, &args).await?;
     println!("[3/4] Checking ETH Claim...");
-    await_or_claim_eth_commitment(state, &offer).await?;
+    let preimage = if args.watch_mempool && offer.is_user_buyer(state.our_pubkey_hash()?) {
+        await_claim_or_pending_preimage(state, &offer, &args).await?
+    } else {
+        await_or_claim_eth_commitment(state, &offer, &args).await?;
+        None
+    };
 
     // if we need to, claim the btc
     if offer.is_user_buyer(state.our_pubkey_hash()?) {
         println!("[4/4] Claiming BTC...");
-        claim_btc_commitment(state, &offer).await?
+        claim_btc_commitment(state, &offer, preimage).await?
     } else {
         println!("[4/4] Swap executed successfully");
     }
@@ -48,13 +86,18 @@ async fn await_or_start_btc_commitment(state: &GauloiState, offer: &Offer) -> Re
             bail!("BTC not committed yet, try again later!");
         }
 
-        let htlc_balance = state.btc_api.get_balance(&htlc_address).unwrap();
+        let htlc_balance = state.btc_api.get_balance(&htlc_address).map_err(|e| anyhow!(e.to_string()))?;
         if offer.is_user_seller(state.our_pubkey_hash()?) && htlc_balance < offer.sold {
+            // never lock up BTC the ETH side couldn't safely claim
+            timelock::check(offer.lockup_btc, offer.lockup_eth)?;
+            // or couldn't commit its ETH against, with the factory paused
+            state.eth_api.ensure_tradeable_for(offer.contract()).await?;
             let remainder = offer.sold - htlc_balance;
             // plus miner fee?
             let ins = state
                 .btc_api
-                .find_unspents_for_value(&our_btc, remainder + fee).unwrap();
+                .find_unspents_for_value(&our_btc, remainder + fee)
+                .map_err(|e| anyhow!(e.to_string()))?;
                 let tx = state.btc_api.build_transaction(
                     state,
                     ins,
@@ -64,7 +107,7 @@ async fn await_or_start_btc_commitment(state: &GauloiState, offer: &Offer) -> Re
                     fee as u64,
                 )?;
             println!("Committing BTC...");
-            state.btc_api.submit_tx(tx).unwrap();
+            state.btc_api.submit_tx(tx).map_err(|e| anyhow!(e.to_string()))?;
             break;
         } else {
             println!("Looking for BTC commitment...");
@@ -77,7 +120,8 @@ async fn await_or_start_btc_commitment(state: &GauloiState, offer: &Offer) -> Re
     Ok(())
 }
 
-async fn await_or_start_eth_commitment(state: &mut GauloiState, offer: &Offer) -> Result<()> {
+async fn await_or_start_eth_commitment(state: &mut GauloiState, offer: &Offer, args: &ExecuteArgs) -> Result<()> {
+    let mut blocks = state.eth_api.block_waiter().await?;
     let mut attempts = 0;
     loop {
         attempts += 1;
@@ -85,33 +129,86 @@ async fn await_or_start_eth_commitment(state: &mut GauloiState, offer: &Offer) -
             break;
         }
 
-        if let Ok(swap) = state.eth_api.our_swap(offer.preimage_hash).await {
-            if swap.value == U256::from(offer.bought) {
+        if let Ok(swap) = state.eth_api.our_swap(offer.preimage_hash, offer.contract()).await {
+            if swap.preimage_hash == offer.preimage_hash {
+                check_eth_commitment(state, offer, &swap).await?;
                 break;
-            } else if swap.preimage_hash == offer.preimage_hash {
-                bail!("swap commitment doesn't have the bought ETH amount")
             }
         }
 
         if offer.is_user_buyer(state.our_pubkey_hash()?) {
+            // never send a second commitment while one is still waiting to be mined
+            ensure_nothing_pending(state, offer, EthTxKind::Commit).await?;
+            // never lock up ETH without time to claim the BTC once the preimage is revealed, the BTC lockup started
+            // when the HTLC confirmed so only what's left of it counts
+            timelock::check(offer.lockup_btc, offer.lockup_eth)?;
+            timelock::check_remaining(remaining_btc_lockup(state, offer).await?, offer.lockup_eth)?;
             // we have to commit
-            let timeout = offer.lockup_eth;
-            let signer = state.get_wallet()?;
-            let seller = H160::from_slice(offer.seller_eth_address.as_slice());
-            let amount = offer.bought;
-            let preimage_hash = offer.preimage_hash;
+            let sender = state.eth_sender(args.offline)?;
             println!("Committing ETH...");
-            state.eth_api.commit_eth(signer, seller, U256::from(amount), preimage_hash, U256::from(timeout)).await?;
+            state.eth_api.commit_eth(sender, &state.db, offer).await?;
             break;
         } else {
             println!("Looking for ETH Commitment...");
         }
-        sleep(Duration::from_secs(10)).await;
+        blocks.next().await;
     }
     Ok(())
 }
 
-async fn await_or_claim_eth_commitment(state: &mut GauloiState, offer: &Offer) -> Result<()> {
+/// Bail if a transaction of this kind for the swap was sent but hasn't been mined yet
+pub async fn ensure_nothing_pending(state: &GauloiState, offer: &Offer, kind: EthTxKind) -> Result<()> {
+    state.eth_api.reconcile_pending_txs(&state.db, state.eth_address()?).await?;
+    if let Some(pending) = state.db.get_pending_eth_tx_for(offer.preimage_hash, kind)? {
+        bail!(
+            "A {:?} transaction for this swap is still pending with nonce {}, wait for it to be mined or run `tx speedup {}`",
+            kind, pending.nonce, pending.nonce
+        )
+    }
+    Ok(())
+}
+
+/// Race the mined claim against a `claimSwap` showing up in the mempool, returning the preimage if it was
+/// seen pending first
+async fn await_claim_or_pending_preimage(state: &GauloiState, offer: &Offer, args: &ExecuteArgs) -> Result<Option<[u8; 32]>> {
+    let swap_id = state.eth_api.our_swap_id(offer.preimage_hash, offer.contract()).await?;
+    println!("Watching pending ETH transactions for the preimage...");
+    tokio::select! {
+        preimage = state.eth_api.watch_pending_preimage(swap_id, offer.preimage_hash, offer.contract()) => {
+            println!("Found the preimage in a pending ETH claim");
+            Ok(Some(preimage?))
+        }
+        claimed = await_or_claim_eth_commitment(state, offer, args) => {
+            claimed?;
+            Ok(None)
+        }
+    }
+}
+
+/// Check the swap committed under the offer's preimage-hash is the one agreed: the bought amount of the offer's token
+/// (or ETH) for the BTC seller, unlocking no later than the agreed ETH lockup. Returns the blocks left until it unlocks
+async fn check_eth_commitment(state: &GauloiState, offer: &Offer, swap: &LockedSwap) -> Result<u64> {
+    if swap.value != U256::from(offer.bought) {
+        bail!("swap commitment doesn't have the bought amount")
+    }
+    let token = offer.token_address.map(Address::from);
+    if swap.token != token {
+        bail!("swap commitment locks {:?} instead of the offer's {:?}", swap.token, token)
+    }
+    let seller = Address::from(offer.seller_eth_address);
+    if swap.buyer != seller {
+        bail!("swap commitment pays {:?} instead of the BTC seller's {:?}", swap.buyer, seller)
+    }
+    let tip = state.eth_api.tip_height().await?.as_u64();
+    let remaining_eth = swap.unlock_block.saturating_sub(U256::from(tip));
+    if remaining_eth > U256::from(offer.lockup_eth) {
+        bail!("swap commitment unlocks in {} blocks, later than the agreed ETH lockup of {} blocks", remaining_eth, offer.lockup_eth)
+    }
+    Ok(remaining_eth.as_u64())
+}
+
+async fn await_or_claim_eth_commitment(state: &GauloiState, offer: &Offer, args: &ExecuteArgs) -> Result<()> {
+    let mut blocks = state.eth_api.block_waiter().await?;
     let mut attempts = 0;
     loop {
         attempts += 1;
@@ -119,8 +216,8 @@ async fn await_or_claim_eth_commitment(state: &mut GauloiState, offer: &Offer) -
             break;
         }
 
-        let swap_id = state.eth_api.our_swap_id(offer.preimage_hash).await?;
-        let swap = state.eth_api.our_swap_by_id(swap_id).await?;
+        let swap_id = state.eth_api.our_swap_id(offer.preimage_hash, offer.contract()).await?;
+        let swap = state.eth_api.our_swap_by_id(swap_id, offer.contract()).await?;
 
         // preimage is 32 bytes of 0 if not claimed by reveal
         if swap.preimage != [0u8;32] {
@@ -128,12 +225,28 @@ async fn await_or_claim_eth_commitment(state: &mut GauloiState, offer: &Offer) -
         }
 
         if offer.is_user_seller(state.our_pubkey_hash()?) {
-            // we have to commit
-            let signer = state.get_wallet()?;
+            if swap.preimage_hash != offer.preimage_hash {
+                bail!("ETH isn't committed yet, try again later!")
+            }
+            // never reveal the preimage for a swap other than the agreed one, or one the committer can refund before
+            // our claim lands, or once the ETH side is left without time to claim the BTC with it
+            let remaining_eth = check_eth_commitment(state, offer, &swap).await?;
+            if remaining_eth == 0 {
+                bail!("swap commitment has already unlocked, claiming it now would race the committer's refund")
+            }
+            timelock::check_remaining(remaining_btc_lockup(state, offer).await?, remaining_eth as u32)?;
+            // we have to claim
             let preimage_opt = state.db.get_preimage(offer.request_hash.as_slice())?;
             if let Some(preimage) = preimage_opt {
+                if let (Some(relayer), SwapContract::Relay) = (&args.relayer, offer.contract()) {
+                    let fee = relayer_fee(offer, args)?;
+                    claim_through_relayer(state, relayer, swap_id, preimage, fee).await?;
+                    break;
+                }
+                let sender = state.eth_sender(args.offline)?;
+                ensure_nothing_pending(state, offer, EthTxKind::Claim).await?;
                 println!("Claiming ETH via preimage...");
-                state.eth_api.claim_eth(signer, swap_id, preimage).await?;
+                state.eth_api.claim_eth(sender, &state.db, swap_id, preimage, offer.contract()).await?;
                 break;
             } else {
                 bail!("No preimage for this swap!")
@@ -141,19 +254,60 @@ async fn await_or_claim_eth_commitment(state: &mut GauloiState, offer: &Offer) -
         } else {
             println!("Looking for ETH claim via preimage...");
         }
-        sleep(Duration::from_secs(10)).await;
+        blocks.next().await;
     }
     Ok(())
 }
 
-async fn claim_btc_commitment(state: &mut GauloiState, offer: &Offer) -> Result<()> {
+/// Blocks left of the BTC lockup, which started when the HTLC confirmed
+async fn remaining_btc_lockup(state: &GauloiState, offer: &Offer) -> Result<u16> {
+    let htlc_address = bitcoin::Address::p2wsh(&offer.htlc_script(), state.network);
+    let confirmed_at = state.btc_api.first_confirmation_height(&htlc_address).await?;
+    let tip = state.btc_api.tip_height().map_err(|e| anyhow!(e.to_string()))?;
+    Ok(timelock::remaining_btc(offer.lockup_btc, confirmed_at, tip))
+}
+
+/// The relayer fee from the arguments, refused if it takes more than [MAX_RELAYER_FEE_PERCENT] of the swap
+fn relayer_fee(offer: &Offer, args: &ExecuteArgs) -> Result<U256> {
+    let fee = parse_ether(&args.relayer_fee)?;
+    let swap_value = U256::from(offer.bought);
+    if fee * 100 > swap_value * MAX_RELAYER_FEE_PERCENT {
+        bail!(
+            "A relayer fee of {} ETH is more than {}% of the {} wei swap, pass a lower --relayer-fee",
+            args.relayer_fee, MAX_RELAYER_FEE_PERCENT, offer.bought
+        )
+    }
+    Ok(fee)
+}
+
+/// Sign an authorization for the relayer to claim our ETH and hand it over, the relayer keeps `fee` for the gas
+async fn claim_through_relayer(state: &GauloiState, relayer: &str, swap_id: U256, preimage: [u8; 32], fee: U256) -> Result<()> {
+    let relayer = relayer.trim_end_matches('/');
+    let http = reqwest::Client::new();
+    let relayer_address: RelayerInfo = http.get(format!("{}/address", relayer)).send().await?.error_for_status()?.json().await?;
+
+    println!("Claiming ETH through relayer {:?}...", relayer_address.address);
+    let claim = state.eth_api.sign_relayed_claim(state.get_wallet()?, swap_id, preimage, fee, relayer_address.address).await?;
+    let response: RelayerResponse = http.post(format!("{}/claim", relayer)).json(&claim).send().await?.json().await?;
+    match response {
+        RelayerResponse::Claimed { tx_hash } => println!("Relayer claimed the ETH in {:?}", tx_hash),
+        RelayerResponse::Failed { error } => bail!("Relayer couldn't claim the ETH: {}", error),
+    }
+    Ok(())
+}
+
+async fn claim_btc_commitment(state: &mut GauloiState, offer: &Offer, pending_preimage: Option<[u8; 32]>) -> Result<()> {
     let mut attempts = 0;
     let htlc_script = offer.htlc_script();
     let htlc_address = bitcoin::Address::p2wsh(&htlc_script, state.network);
     let our_btc = state.p2wpkh_address()?;
 
-    let swap = state.eth_api.our_swap(offer.preimage_hash).await?;
-    if swap.preimage == [0u8;32] { // expect this is already here
+    // use the preimage from a pending claim if we saw one, it's already checked against the preimage-hash
+    let preimage = match pending_preimage {
+        Some(preimage) => preimage,
+        None => state.eth_api.our_swap(offer.preimage_hash, offer.contract()).await?.preimage,
+    };
+    if preimage == [0u8;32] { // expect this is already here
         bail!("Preimage isn't committed yet!")
     }
 
@@ -165,25 +319,45 @@ async fn claim_btc_commitment(state: &mut GauloiState, offer: &Offer) -> Result<
         }
 
 
-        let htlc_balance = state.btc_api.get_balance(&htlc_address).unwrap();
         if offer.is_user_buyer(state.our_pubkey_hash()?) {
             println!("Claiming BTC...");
             // plus miner fee?
             let ins = state
                 .btc_api
-                .get_utxos(&htlc_address).unwrap();
+                .get_utxos(&htlc_address)
+                .map_err(|e| anyhow!(e.to_string()))?;
             let tx = state.btc_api.build_claim_btc(
                 state,
                 ins,
                 &our_btc,
                 htlc_script.clone(),
-                swap.preimage,
+                preimage,
                 fee as u64,
             )?;
-            state.btc_api.submit_tx(tx).unwrap();
+            state.btc_api.submit_tx(tx).map_err(|e| anyhow!(e.to_string()))?;
             break;
         }
         sleep(Duration::from_secs(10)).await;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relayed_with_fee(fee: &str) -> ExecuteArgs {
+        ExecuteArgs::try_parse_from(["execute", "0", "--relayer", "http://localhost:8545", "--relayer-fee", fee]).unwrap()
+    }
+
+    #[test]
+    fn relayer_fee_is_capped_at_a_share_of_the_swap() {
+        // the sample swap is for 20 ETH, so the relayer can keep up to 1 ETH
+        let offer = Offer::sample();
+        assert_eq!(relayer_fee(&offer, &relayed_with_fee("1")).unwrap(), parse_ether("1").unwrap());
+        assert_eq!(relayer_fee(&offer, &relayed_with_fee("0.001")).unwrap(), parse_ether("0.001").unwrap());
+        assert!(relayer_fee(&offer, &relayed_with_fee("1.01")).is_err());
+        assert!(relayer_fee(&offer, &relayed_with_fee("not a number")).is_err());
+    }
+}
+
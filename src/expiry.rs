@@ -0,0 +1,80 @@
+//! Offer expiry, so a stale offer can't be accepted at a rate that no longer makes sense
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::bitcoin_api::BitcoinApi;
+use crate::offer::{OfferExpiry, OfferStatus};
+use crate::swaps::SwapStorage;
+
+/// How often the background task looks for pending offers that have expired
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum ExpiryError {
+    #[error("The offer expired at {0}")]
+    Expired(OfferExpiry),
+    #[error("The offer has been marked as expired")]
+    MarkedExpired,
+}
+
+/// The expiry for an offer created now, at a BTC block height if one is given or else `minutes` from now
+pub fn for_new_offer(minutes: u64, btc_height: Option<u32>) -> Result<OfferExpiry> {
+    Ok(match btc_height {
+        Some(height) => OfferExpiry::BtcHeight(height),
+        None => OfferExpiry::Timestamp(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + minutes * 60),
+    })
+}
+
+/// Whether the expiry has passed, only looking up the BTC tip if it's a block height
+pub fn has_passed(btc_api: &BitcoinApi, expiry: OfferExpiry) -> Result<bool> {
+    let btc_height = match expiry {
+        OfferExpiry::BtcHeight(_) => btc_api.tip_height().map_err(|e| anyhow!(e.to_string()))?,
+        OfferExpiry::Timestamp(_) => 0,
+    };
+    Ok(expiry.has_passed(btc_height))
+}
+
+/// Fail if an offer with the given expiry can no longer be accepted
+pub fn check(btc_api: &BitcoinApi, expiry: Option<OfferExpiry>) -> Result<()> {
+    match expiry {
+        Some(expiry) if has_passed(btc_api, expiry)? => Err(ExpiryError::Expired(expiry).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Mark pending offer requests that have passed their expiry without a response, returning how many were marked
+pub fn mark_expired(db: &SwapStorage, btc_api: &BitcoinApi) -> Result<usize> {
+    let mut marked = 0;
+    let btc_offers = db.get_pending_offers()?.into_iter().map(|(hash, request)| (hash, request.expiry));
+    let eth_offers = db.get_pending_eth_offers()?.into_iter().map(|(hash, request)| (hash, request.expiry));
+    for (hash, expiry) in btc_offers.chain(eth_offers) {
+        let Some(expiry) = expiry else {
+            continue;
+        };
+        if db.get_offer_status(&hash)?.is_some() || db.has_offer(&hash)? {
+            continue;
+        }
+        if has_passed(btc_api, expiry)? {
+            db.set_offer_status(&hash, OfferStatus::Expired)?;
+            marked += 1;
+        }
+    }
+    Ok(marked)
+}
+
+/// Keep marking expired offers in the background for as long as the shell is open
+pub async fn watch(db: SwapStorage) {
+    let btc_api = BitcoinApi::default();
+    let mut interval = tokio::time::interval(EXPIRY_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        // the esplora client blocks, so keep it off the runtime's other tasks
+        if let Err(e) = tokio::task::block_in_place(|| mark_expired(&db, &btc_api)) {
+            eprintln!("{} couldn't check offer expiry: {}", Paint::yellow("Warning:"), e);
+        }
+    }
+}
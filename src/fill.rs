@@ -0,0 +1,114 @@
+//! Partial fills of fillable offers, where each fill becomes a swap of its own with its own preimage
+
+use anyhow::{bail, Result};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::Amount;
+use clap::Args;
+use thiserror::Error;
+
+use crate::envelope::{self, SignedEnvelope};
+use crate::message::{self, Message};
+use crate::offer::{FillConfirm, Offer, OfferRequest, OfferResponse};
+use crate::qr::QrArgs;
+use crate::state::GauloiState;
+use crate::timelock;
+
+/// Options for a message sent back in reply to the one being imported
+#[derive(Args, Debug)]
+pub struct ReplyArgs {
+    /// Sign our reply with our ETH key as well as our BTC key
+    #[clap(long)]
+    pub sign_eth: bool,
+
+    #[clap(flatten)]
+    pub qr: QrArgs,
+}
+
+#[derive(Error, Debug)]
+pub enum FillError {
+    #[error("The fill of {fill} is more than the {remaining} left of the offer")]
+    TooLarge { fill: Amount, remaining: Amount },
+    #[error("The fill of {fill} is less than the minimum of {min}")]
+    TooSmall { fill: Amount, min: Amount },
+    #[error("The fill doesn't buy at the offer's rate")]
+    WrongRate,
+    #[error("This fill has already been confirmed")]
+    AlreadyFilled,
+    #[error("The fill confirmation is for another taker")]
+    NotOurs,
+    #[error("The fill confirmation doesn't match the amounts, lockup or ETH address we responded with")]
+    Altered,
+}
+
+/// Confirm a taker's fill of a fillable offer we made, making the preimage for the fill's swap and printing the
+/// confirmation to send back
+pub async fn confirm_fill(state: &GauloiState, reply: &ReplyArgs, request: OfferRequest, response: OfferResponse) -> Result<Offer> {
+    let Some(mut fill_state) = state.db.get_fill_state(&response.request_hash)? else {
+        bail!("No fills of this offer are tracked locally")
+    };
+    let fill_hash = message::request_hash(&response)?;
+    if fill_state.fills.contains(&fill_hash) {
+        return Err(FillError::AlreadyFilled.into());
+    }
+
+    let fill = Amount::from_sat(response.sold as u64);
+    let remaining = Amount::from_sat(fill_state.remaining as u64);
+    let min = Amount::from_sat(request.min_fill.unwrap_or_default() as u64);
+    if fill > remaining {
+        return Err(FillError::TooLarge { fill, remaining }.into());
+    }
+    // whatever's left can always be filled, even once it's below the minimum
+    if fill < min && fill != remaining {
+        return Err(FillError::TooSmall { fill, min }.into());
+    }
+    if response.bought < request.bought_for(response.sold) {
+        return Err(FillError::WrongRate.into());
+    }
+    timelock::check(request.lockup_btc, response.lockup_eth)?;
+
+    let preimage = GauloiState::preimage();
+    state.db.add_preimage_for(&fill_hash, preimage)?;
+    let confirm = FillConfirm {
+        request_hash: response.request_hash,
+        fill_hash,
+        sold: response.sold,
+        bought: response.bought,
+        lockup_eth: response.lockup_eth,
+        buyer_pubkey_hash: response.buyer_pubkey_hash,
+        buyer_eth_address: response.buyer_eth_address,
+        preimage_hash: sha256::Hash::hash(&preimage).to_byte_array(),
+    };
+    let complete_offer = state.db.add_fill(&request, &confirm)?;
+
+    fill_state.remaining -= response.sold;
+    fill_state.fills.push(fill_hash);
+    state.db.put_fill_state(&response.request_hash, &fill_state)?;
+    println!("Filled {}, {} of the offer left", fill, Amount::from_sat(fill_state.remaining as u64));
+
+    let message = Message::Fill(confirm);
+    SignedEnvelope::publish(state, &message, reply.sign_eth, &reply.qr, "fill confirmation").await?;
+    Ok(complete_offer)
+}
+
+/// Take in the maker's confirmation of our fill of their offer, which has to be for exactly the fill we responded with
+pub fn import_fill(state: &GauloiState, fill: FillConfirm, signed: Option<SignedEnvelope>, allow_unsigned: bool) -> Result<Offer> {
+    let Some(request) = state.db.get_pending_offer(&fill.request_hash)? else {
+        bail!("No offer / request locally")
+    };
+    envelope::check_sender(state, signed.as_ref(), request.seller_pubkey_hash, request.seller_eth_address, allow_unsigned)?;
+    let Some(response) = state.db.get_fill_response(&fill.fill_hash)? else {
+        return Err(FillError::NotOurs.into());
+    };
+    if response.request_hash != fill.request_hash || fill.buyer_pubkey_hash != state.our_pubkey_hash()? {
+        return Err(FillError::NotOurs.into());
+    }
+    if (fill.sold, fill.bought, fill.lockup_eth, fill.buyer_eth_address)
+        != (response.sold, response.bought, response.lockup_eth, response.buyer_eth_address)
+    {
+        return Err(FillError::Altered.into());
+    }
+    if state.db.has_offer(&fill.fill_hash)? {
+        return Err(FillError::AlreadyFilled.into());
+    }
+    state.db.add_fill(&request, &fill)
+}
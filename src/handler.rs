@@ -17,7 +17,7 @@ impl<T: Send> AsyncHandler<T> for GauloiAsyncHandler {
         state: &mut T,
         description: &str,
     ) -> bool {
-        if let Some(command) = line.get(0) {
+        if let Some(command) = line.first() {
             let command_str = command.as_str();
             match command_str {
                 "quit" | "q" => return true,
@@ -42,11 +42,14 @@ impl<T: Send> AsyncHandler<T> for GauloiAsyncHandler {
                             } {
                                 eprintln!(
                                     "{}",
-                                    Paint::red(format!(
-                                        "Command exited unsuccessfully:\n{}\n({:?})",
-                                        &e, &e
-                                    ))
-                                )
+                                    Paint::red(format!("Command exited unsuccessfully:\n{}", &e))
+                                );
+                                // print the readable causes rather than dumping the whole error
+                                let mut source = e.source();
+                                while let Some(cause) = source {
+                                    eprintln!("{}", Paint::red(format!("  caused by: {}", cause)));
+                                    source = cause.source();
+                                }
                             }
                         }
                         None => {
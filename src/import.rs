@@ -1,15 +1,27 @@
-use ciborium::from_reader;
 use clap::Parser;
-use hex;
 
+use crate::cancel::OfferCancelledError;
+use crate::encoding;
+use crate::envelope;
+use crate::expiry::{self, ExpiryError};
+use crate::fill::{self, ReplyArgs};
+use crate::message::{self, Message};
 use crate::offer::*;
 use crate::state::GauloiState;
+use crate::timelock;
 
 /// Import an offer response
 #[derive(Parser, Debug)]
 pub struct ImportOfferArgs {
-    /// Hex encoded offer response
+    /// The offer response, or its hex from older clients
     offer: String,
+
+    /// Accept a response that isn't signed by the other side of the swap
+    #[clap(long)]
+    allow_unsigned: bool,
+
+    #[clap(flatten)]
+    reply: ReplyArgs,
 }
 
 pub async fn import_offer_response(
@@ -17,10 +29,49 @@ pub async fn import_offer_response(
     args: ImportOfferArgs) -> Result<(), anyhow::Error> {
     println!();
 
-    let bytes = hex::decode(args.offer)?;
-    let offer_response: OfferResponse = from_reader(bytes.as_slice())?;
+    let (bytes, signed) = envelope::open(encoding::decode_as(&args.offer, &["response", "fill"])?);
+    let message = Message::decode(&bytes)?;
+    let complete_offer = if let Message::Fill(fill) = message {
+        // the maker's confirmation of our fill of their offer
+        fill::import_fill(state, fill, signed, args.allow_unsigned)?
+    } else if let Message::EthResponse(response) = message {
+        // a response to an offer we made from the ETH side, sent by the BTC seller
+        envelope::check_sender(
+            state,
+            signed.as_ref(),
+            response.seller_pubkey_hash,
+            response.seller_eth_address,
+            args.allow_unsigned,
+        )?;
+        if let Some(request) = state.db.get_pending_eth_offer(&response.request_hash)? {
+            check_still_open(state, &response.request_hash, request.expiry)?;
+            // the BTC side picks its own lockup when it accepts, so it can't be trusted to outlast ours
+            timelock::check(response.lockup_btc, request.lockup_eth)?;
+        }
+        state.db.add_eth_offer_response(&response)?
+    } else {
+        let offer_response = message::decode_response(&bytes)?;
+        envelope::check_sender(
+            state,
+            signed.as_ref(),
+            offer_response.buyer_pubkey_hash,
+            offer_response.buyer_eth_address,
+            args.allow_unsigned,
+        )?;
+        match state.db.get_pending_offer(&offer_response.request_hash)? {
+            Some(request) => {
+                check_still_open(state, &offer_response.request_hash, request.expiry)?;
+                if request.min_fill.is_some() {
+                    fill::confirm_fill(state, &args.reply, request, offer_response).await?
+                } else {
+                    timelock::check(request.lockup_btc, offer_response.lockup_eth)?;
+                    state.db.add_offer_response(offer_response)?
+                }
+            }
+            None => state.db.add_offer_response(offer_response)?,
+        }
+    };
 
-    let complete_offer = state.db.add_offer_response(offer_response)?;
     let index = state.db.get_swap_index(&complete_offer)?.unwrap();
     println!("Offer imported successfully!");
     println!("Swap index: {}", index);
@@ -28,5 +79,12 @@ pub async fn import_offer_response(
     Ok(())
 }
 
-
-
+/// Fail if the request a response is for has been cancelled, or has expired or been marked as expired
+fn check_still_open(state: &GauloiState, request_hash: &[u8; 32], expiry: Option<OfferExpiry>) -> Result<(), anyhow::Error> {
+    match state.db.get_offer_status(request_hash)? {
+        Some(OfferStatus::Expired) => return Err(ExpiryError::MarkedExpired.into()),
+        Some(OfferStatus::Cancelled) => return Err(OfferCancelledError {}.into()),
+        None => {}
+    }
+    expiry::check(&state.btc_api, expiry)
+}
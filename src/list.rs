@@ -1,6 +1,3 @@
-use std::ops::Index;
-
-use bitcoin::hashes::{sha256, Hash};
 use clap::Parser;
 
 use crate::state::GauloiState;
@@ -19,15 +16,40 @@ pub async fn print_all(state: &mut GauloiState, _args: ListArgs) -> Result<(), a
     all_swaps.iter().enumerate().for_each(|(index, swap)| {
         let mut bytes_writer = Vec::new();
         ciborium::into_writer(&swap, &mut bytes_writer).unwrap();
-        println!("[{}] Swap: Buy {}ETH / Sell {}BTC, lockup: {}, preimage-hash: {}, ready to execute? {}",
+        let asset = match swap.token_address {
+            Some(token) => format!(" of token {}", hex::encode(token)),
+            None => "ETH".to_string(),
+        };
+        println!("[{}] Swap: Buy {}{} / Sell {}BTC, lockup: {}, preimage-hash: {}, ready to execute? {}",
             index,
-            ethers::utils::format_ether(swap.bought),
+            crate::offer::format_bought(swap.bought, swap.token_decimals),
+            asset,
             bitcoin::Amount::from_sat(swap.sold as u64).to_btc(),
             swap.lockup_btc,
             hex::encode(swap.preimage_hash),
-            true
+            !swap.recovered
         );
+        if swap.eth_initiated {
+            println!("    Offered by the ETH side");
+        }
+        if let Some(status) = swap.eth_status {
+            println!("    ETH side: {:?}{}", status, if swap.recovered { " (recovered from chain)" } else { "" });
+        }
     });
     
+
+    for (hash, request) in state.db.get_pending_offers()? {
+        let (Some(min_fill), Some(fill_state)) = (request.min_fill, state.db.get_fill_state(&hash)?) else {
+            continue;
+        };
+        println!("Fillable offer {}: Sell {}BTC, {}BTC left in fills of at least {}BTC, {} filled so far",
+            hex::encode(hash),
+            bitcoin::Amount::from_sat(request.sold as u64).to_btc(),
+            bitcoin::Amount::from_sat(fill_state.remaining as u64).to_btc(),
+            bitcoin::Amount::from_sat(min_fill as u64).to_btc(),
+            fill_state.fills.len()
+        );
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -13,6 +13,9 @@ use swaps::SwapStorage;
 use rand::prelude::ThreadRng;
 
 use create::CreateOfferArgs;
+use create_eth::CreateEthOfferArgs;
+use counter::CounterArgs;
+use cancel::CancelArgs;
 use handler::GauloiAsyncHandler;
 
 use list::ListArgs;
@@ -20,8 +23,23 @@ use parse::ParseOfferArgs;
 use state::GauloiState;
 use import::ImportOfferArgs;
 use execute::ExecuteArgs;
+use doctor::DoctorArgs;
+use contract::ContractArgs;
+use config::GauloiConfig;
+use tx::TxArgs;
+use wallet::WalletArgs;
+use refund::RefundArgs;
+use broadcast::BroadcastArgs;
+use relayer::RelayArgs;
+use scan::ScanEthArgs;
+use status::StatusArgs;
+use yansi::Paint;
 
 pub mod create;
+pub mod create_eth;
+pub mod counter;
+pub mod fill;
+pub mod cancel;
 pub mod handler;
 pub mod offer;
 pub mod state;
@@ -29,10 +47,28 @@ pub mod parse;
 pub mod address;
 pub mod bitcoin_api;
 pub mod eth_api;
+pub mod eth_transport;
 pub mod import;
 pub mod swaps;
 pub mod list;
 pub mod execute;
+pub mod doctor;
+pub mod config;
+pub mod contract;
+pub mod tx;
+pub mod refund;
+pub mod wallet;
+pub mod broadcast;
+pub mod relayer;
+pub mod scan;
+pub mod price;
+pub mod envelope;
+pub mod expiry;
+pub mod message;
+pub mod encoding;
+pub mod qr;
+pub mod status;
+pub mod timelock;
 
 pub const NETWORK: Network = Network::Bitcoin;
 
@@ -57,16 +93,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ExtendedPrivKey::decode(key.as_slice())?
     };
 
+    let config = GauloiConfig::load()?;
+    let eth_signer = match &config.eth.keystore {
+        Some(path) => match wallet::unlock_keystore(path) {
+            Ok(wallet) => Some(wallet),
+            Err(e) => {
+                eprintln!("{} couldn't unlock ETH keystore {}: {}, using the hot wallet ETH account this session", Paint::red("Warning:"), path, e);
+                None
+            }
+        },
+        None => None,
+    };
+
     let our_state = GauloiState {
         editor,
         secp,
         master_extended: extended,
         network: NETWORK,
         btc_api: BitcoinApi::default(),
-        eth_api: EthApi::mainnet(),
+        eth_api: EthApi::from_config(&config.eth).await?,
         db: SwapStorage::default(),
+        config,
+        eth_signer,
     };
 
+    // Don't trust whatever is deployed at the configured address
+    match our_state.eth_api.ensure_tradeable().await {
+        Ok(()) => {}
+        Err(e) => eprintln!("{} {}\nRun `doctor` for details", Paint::red("Warning:"), e),
+    }
+
+    // Make sure anything sent last time is accounted for before we send anything else
+    if let Err(e) = tx::reconcile(&our_state).await {
+        eprintln!("{} couldn't check pending ETH transactions: {}", Paint::red("Warning:"), e);
+    }
+
+    tokio::spawn(expiry::watch(our_state.db.clone()));
+
     let mut shell = Shell::new_with_async_handler(
         our_state,
         "gauloi-cli$ ",
@@ -84,11 +147,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         clap_command!(GauloiState, CreateOfferArgs, async create::create_offer),
     );
 
+    shell.commands.insert(
+        "create-eth",
+        clap_command!(GauloiState, CreateEthOfferArgs, async create_eth::create_eth_offer),
+    );
+
     shell.commands.insert(
         "parse",
         clap_command!(GauloiState, ParseOfferArgs, async parse::parse_offer),
     );
 
+    shell.commands.insert(
+        "counter",
+        clap_command!(GauloiState, CounterArgs, async counter::counter),
+    );
+
+    shell.commands.insert(
+        "cancel",
+        clap_command!(GauloiState, CancelArgs, async cancel::cancel),
+    );
+
     shell.commands.insert(
         "list",
         clap_command!(GauloiState, ListArgs, async list::print_all),
@@ -99,11 +177,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         clap_command!(GauloiState, ImportOfferArgs, async import::import_offer_response),
     );
 
+    shell.commands.insert(
+        "status",
+        clap_command!(GauloiState, StatusArgs, async status::status),
+    );
+
     shell.commands.insert(
         "execute",
         clap_command!(GauloiState, ExecuteArgs, async execute::execute),
     );
 
+    shell.commands.insert(
+        "refund",
+        clap_command!(GauloiState, RefundArgs, async refund::refund),
+    );
+
+    shell.commands.insert(
+        "eth-broadcast",
+        clap_command!(GauloiState, BroadcastArgs, async broadcast::broadcast),
+    );
+
+    shell.commands.insert(
+        "relay",
+        clap_command!(GauloiState, RelayArgs, async relayer::relay),
+    );
+
+    shell.commands.insert(
+        "scan-eth",
+        clap_command!(GauloiState, ScanEthArgs, async scan::scan_eth),
+    );
+
+    shell.commands.insert(
+        "wallet",
+        clap_command!(GauloiState, WalletArgs, async wallet::wallet),
+    );
+
+    shell.commands.insert(
+        "doctor",
+        clap_command!(GauloiState, DoctorArgs, async doctor::doctor),
+    );
+
+    shell.commands.insert(
+        "contract",
+        clap_command!(GauloiState, ContractArgs, async contract::contract),
+    );
+
+    shell.commands.insert(
+        "tx",
+        clap_command!(GauloiState, TxArgs, async tx::tx),
+    );
+
     shell.run_async().await?;
 
     Ok(())
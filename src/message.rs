@@ -0,0 +1,194 @@
+//! Versioned wire format for the messages passed between the two sides of a swap
+
+use anyhow::Result;
+use bitcoin::hashes::{sha256, Hash};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::offer::{CounterAccept, EthOfferRequest, EthOfferResponse, FillConfirm, OfferCancel, OfferCounter, OfferRequest, OfferResponse, LEGACY_VERSION, V1_VERSION, VERSION};
+
+#[derive(Error, Debug)]
+pub enum MessageError {
+    #[error("Message version {0} isn't supported, this client reads versions {LEGACY_VERSION} to {VERSION} so may need updating")]
+    UnsupportedVersion(u8),
+    #[error("Expected an offer {expected} but got an offer {got}")]
+    UnexpectedKind { expected: &'static str, got: &'static str },
+    #[error("Message isn't well formed cbor")]
+    Malformed,
+}
+
+/// Everything that can be sent to the other side of a swap
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+pub enum Message {
+    Request(OfferRequest),
+    Response(OfferResponse),
+    Cancel(OfferCancel),
+    EthRequest(EthOfferRequest),
+    EthResponse(EthOfferResponse),
+    Counter(OfferCounter),
+    Accept(CounterAccept),
+    Fill(FillConfirm),
+}
+
+/// A [Message] as it's encoded on the wire from version 1, version 2 only widened the lockups
+#[derive(Serialize, Deserialize)]
+struct VersionedMessage {
+    version: u8,
+    message: Message,
+}
+
+/// Just the version of a message, every version has it as a top level field
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u8,
+}
+
+impl Message {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Message::Request(_) | Message::EthRequest(_) => "request",
+            Message::Response(_) | Message::EthResponse(_) => "response",
+            Message::Cancel(_) => "cancel",
+            Message::Counter(_) => "counter",
+            Message::Accept(_) => "accept",
+            Message::Fill(_) => "fill",
+        }
+    }
+
+    /// Encode the message with the current version
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&VersionedMessage { version: VERSION, message: self.clone() }, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Decode a message of any version we can read
+    pub fn decode(bytes: &[u8]) -> Result<Message> {
+        let header: VersionHeader = ciborium::from_reader(bytes)?;
+        match header.version {
+            LEGACY_VERSION => {
+                // legacy messages are untyped, but a request and response never have the same fields
+                if let Ok(request) = ciborium::from_reader(bytes) {
+                    Ok(Message::Request(request))
+                } else {
+                    Ok(Message::Response(ciborium::from_reader(bytes)?))
+                }
+            }
+            V1_VERSION | VERSION => Ok(ciborium::from_reader::<VersionedMessage, _>(bytes)?.message),
+            version => Err(MessageError::UnsupportedVersion(version).into()),
+        }
+    }
+
+    fn unexpected(self, expected: &'static str) -> anyhow::Error {
+        MessageError::UnexpectedKind { expected, got: self.kind() }.into()
+    }
+}
+
+/// The hash a request is stored under, SHA256 of its cbor bytes
+pub fn request_hash<T: Serialize>(request: &T) -> Result<[u8; 32]> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(request, &mut bytes)?;
+    Ok(sha256::Hash::hash(&bytes).to_byte_array())
+}
+
+/// Decode an offer request with the hash it's stored under by its creator
+pub fn decode_request(bytes: &[u8]) -> Result<(OfferRequest, [u8; 32])> {
+    let legacy = ciborium::from_reader::<VersionHeader, _>(bytes)?.version == LEGACY_VERSION;
+    match Message::decode(bytes)? {
+        Message::Request(request) => {
+            // legacy requests were hashed as sent, newer ones by the request alone, taken as sent from the envelope
+            let hash = if legacy {
+                sha256::Hash::hash(bytes).to_byte_array()
+            } else {
+                body_hash(bytes)?
+            };
+            Ok((request, hash))
+        }
+        other => Err(other.unexpected("request")),
+    }
+}
+
+/// The hash a received request is stored under, SHA256 of its body as it was sent rather than as we'd encode it
+pub fn body_hash(bytes: &[u8]) -> Result<[u8; 32]> {
+    Ok(sha256::Hash::hash(message_body(bytes)?).to_byte_array())
+}
+
+/// The exact bytes of the message body in a versioned envelope, `{version, message: {type, body}}`
+fn message_body(bytes: &[u8]) -> Result<&[u8]> {
+    let message = map_value(bytes, "message")?;
+    map_value(message, "body")
+}
+
+/// The bytes of the value under a text key in the cbor map at the start of `bytes`
+fn map_value<'a>(bytes: &'a [u8], key: &str) -> Result<&'a [u8]> {
+    let (major, len, mut pos) = cbor_head(bytes, 0)?;
+    if major != 5 {
+        return Err(MessageError::Malformed.into());
+    }
+    let mut entries = 0;
+    while len.map_or(bytes.get(pos) != Some(&0xff), |len| entries < len) {
+        let key_end = cbor_item_end(bytes, pos)?;
+        let value_end = cbor_item_end(bytes, key_end)?;
+        let (key_major, _, key_start) = cbor_head(bytes, pos)?;
+        if key_major == 3 && &bytes[key_start..key_end] == key.as_bytes() {
+            return Ok(&bytes[key_end..value_end]);
+        }
+        pos = value_end;
+        entries += 1;
+    }
+    Err(MessageError::Malformed.into())
+}
+
+/// Read the head of the cbor item at `pos`: its major type, its argument ([None] for indefinite lengths) and where
+/// its content starts
+fn cbor_head(bytes: &[u8], pos: usize) -> Result<(u8, Option<u64>, usize)> {
+    let initial = *bytes.get(pos).ok_or(MessageError::Malformed)?;
+    let (major, info) = (initial >> 5, initial & 0x1f);
+    let size = match info {
+        0..=23 => return Ok((major, Some(info as u64), pos + 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        31 => return Ok((major, None, pos + 1)),
+        _ => return Err(MessageError::Malformed.into()),
+    };
+    let arg = bytes.get(pos + 1..pos + 1 + size).ok_or(MessageError::Malformed)?;
+    Ok((major, Some(arg.iter().fold(0, |acc, byte| acc << 8 | *byte as u64)), pos + 1 + size))
+}
+
+/// Where the cbor item starting at `pos` ends
+fn cbor_item_end(bytes: &[u8], pos: usize) -> Result<usize> {
+    let (major, arg, mut end) = cbor_head(bytes, pos)?;
+    match (major, arg) {
+        (0 | 1 | 7, Some(_)) => {}
+        (2 | 3, Some(len)) => end = end.checked_add(len as usize).ok_or(MessageError::Malformed)?,
+        (4 | 5, Some(len)) => {
+            let items = if major == 5 { len.saturating_mul(2) } else { len };
+            for _ in 0..items {
+                end = cbor_item_end(bytes, end)?;
+            }
+        }
+        (6, Some(_)) => end = cbor_item_end(bytes, end)?,
+        // indefinite length strings, arrays and maps run until a break
+        (2..=5, None) => {
+            while bytes.get(end) != Some(&0xff) {
+                end = cbor_item_end(bytes, end)?;
+            }
+            end += 1;
+        }
+        _ => return Err(MessageError::Malformed.into()),
+    }
+    if end > bytes.len() {
+        return Err(MessageError::Malformed.into());
+    }
+    Ok(end)
+}
+
+pub fn decode_response(bytes: &[u8]) -> Result<OfferResponse> {
+    match Message::decode(bytes)? {
+        Message::Response(response) => Ok(response),
+        other => Err(other.unexpected("response")),
+    }
+}
@@ -1,7 +1,32 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use bitcoin::{ScriptBuf, Script, opcodes::all::*};
+use ethers::types::{H160, U256};
 use serde::{Deserialize, Serialize};
 
-pub const VERSION: u8 = 0;
+use crate::eth_api::SwapContract;
+
+/// Version of the request / response agreement protocol we write
+pub const VERSION: u8 = 2;
+
+/// Typed messages from before lockups were widened to u16 BTC and u32 ETH blocks, their narrower lockups still decode
+/// into the wider fields
+pub const V1_VERSION: u8 = 1;
+
+/// Oldest version we can still read, bare CBOR requests and responses without a message type
+pub const LEGACY_VERSION: u8 = 0;
+
+/// Decimals of native ETH, the default for offers from before ERC-20 swaps
+pub const ETH_DECIMALS: u8 = 18;
+
+fn eth_decimals() -> u8 {
+    ETH_DECIMALS
+}
+
+/// Format an amount of the bought asset in whole units, e.g. ETH instead of wei
+pub fn format_bought(amount: u128, decimals: u8) -> String {
+    ethers::utils::format_units(U256::from(amount), decimals as u32).unwrap_or_else(|_| amount.to_string())
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct OfferRequest {
@@ -9,16 +34,216 @@ pub struct OfferRequest {
     pub version: u8,
     /// Amount of BTC sold
     pub sold: u128,
-    /// Amount of ETH bought
+    /// Amount of ETH (wei) or tokens (base units) bought
     pub bought: u128,
     /// Lockup time of BTC in HTLC (number of blocks)
-    pub lockup_btc: u8,
+    pub lockup_btc: u16,
     /// Our Bitcoin redemption address's HASH_160'd pubkey (RIPEMD_160(SHA256)) for timeout claim
     pub seller_pubkey_hash: [u8; 20],
     /// Our ETH redemption address
     pub seller_eth_address: [u8; 20],
-    /// The preimage-hash
+    /// The preimage-hash, all zeros for fillable offers as each fill gets its own
+    pub preimage_hash: [u8; 32],
+    /// The ERC-20 token bought instead of native ETH, if any
+    #[serde(default)]
+    pub token_address: Option<[u8; 20]>,
+    /// Decimals of the bought asset
+    #[serde(default = "eth_decimals")]
+    pub token_decimals: u8,
+    /// The ETH is locked in the GauloiRelayFactory, so the seller can claim it through a relayer
+    #[serde(default)]
+    pub relayed: bool,
+    /// When the offer stops being valid, offers from before expiry was added never expire
+    #[serde(default)]
+    pub expiry: Option<OfferExpiry>,
+    /// Smallest amount of BTC (sats) a taker can fill, if several takers can each fill part of the offer
+    #[serde(default)]
+    pub min_fill: Option<u128>,
+}
+
+impl OfferRequest {
+    /// The ETH (wei) or tokens (base units) bought for part of the BTC sold, at the offer's rate
+    pub fn bought_for(&self, sold: u128) -> u128 {
+        (U256::from(self.bought) * U256::from(sold) / U256::from(self.sold)).as_u128()
+    }
+}
+
+/// The maker's confirmation of a fill of a fillable offer, with the preimage-hash of the fill's own swap
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FillConfirm {
+    /// The SHA256 hash of the offer request's cbor bytes
+    pub request_hash: [u8; 32],
+    /// The SHA256 hash of the taker's response, the fill's swap is kept under it
+    pub fill_hash: [u8; 32],
+    /// Amount of BTC sold in the fill (sats)
+    pub sold: u128,
+    /// Amount of ETH (wei) or tokens (base units) bought in the fill
+    pub bought: u128,
+    /// Blocks that ETH will be locked up for
+    pub lockup_eth: u32,
+    /// The taker's pubkey HASH_160'd
+    pub buyer_pubkey_hash: [u8; 20],
+    /// The taker's ETH address
+    pub buyer_eth_address: [u8; 20],
+    /// The preimage-hash for the fill, the maker keeps the preimage
+    pub preimage_hash: [u8; 32],
+}
+
+/// How much of a fillable offer we made is left, and the fills taken from it
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FillState {
+    /// BTC left to fill (sats)
+    pub remaining: u128,
+    /// Hashes of the responses filled so far
+    pub fills: Vec<[u8; 32]>,
+}
+
+/// The point after which an offer can no longer be accepted
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfferExpiry {
+    /// Unix timestamp in seconds
+    Timestamp(u64),
+    /// Bitcoin block height
+    BtcHeight(u32),
+}
+
+impl OfferExpiry {
+    /// Whether the expiry has passed, as of now and the given BTC tip height
+    pub fn has_passed(&self, btc_height: u32) -> bool {
+        match self {
+            OfferExpiry::Timestamp(timestamp) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+                now >= *timestamp
+            }
+            OfferExpiry::BtcHeight(height) => btc_height >= *height,
+        }
+    }
+}
+
+impl std::fmt::Display for OfferExpiry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OfferExpiry::Timestamp(timestamp) => write!(f, "unix time {}", timestamp),
+            OfferExpiry::BtcHeight(height) => write!(f, "BTC block {}", height),
+        }
+    }
+}
+
+/// Where a pending offer request is at, kept separately from the request itself
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfferStatus {
+    /// Passed its expiry without a response being imported
+    Expired,
+    /// Withdrawn by its maker
+    Cancelled,
+}
+
+/// An offer created by the ETH side of a swap, which the BTC side accepts with an [EthOfferResponse]. The BTC side
+/// still makes the preimage when it accepts, as it locks first and with the longer timelock either way
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EthOfferRequest {
+    /// Version the request / response agreement protocol for backwards incompatibility (potentially)
+    pub version: u8,
+    /// Amount of BTC bought (sats)
+    pub sold: u128,
+    /// Amount of ETH (wei) or tokens (base units) sold
+    pub bought: u128,
+    /// Blocks that ETH will be locked up for
+    pub lockup_eth: u32,
+    /// Our Bitcoin address's HASH_160'd pubkey, the BTC is claimed to it
+    pub buyer_pubkey_hash: [u8; 20],
+    /// Our ETH address, the ETH is committed from it
+    pub buyer_eth_address: [u8; 20],
+    /// The ERC-20 token sold instead of native ETH, if any
+    pub token_address: Option<[u8; 20]>,
+    /// Decimals of the sold asset
+    pub token_decimals: u8,
+    /// The ETH is locked in the GauloiRelayFactory, so the BTC side can claim it through a relayer
+    pub relayed: bool,
+    /// When the offer stops being valid
+    pub expiry: Option<OfferExpiry>,
+}
+
+/// The BTC side's acceptance of an [EthOfferRequest]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct EthOfferResponse {
+    /// Version the request / response agreement protocol for backwards incompatibility (potentially)
+    pub version: u8,
+    /// Amount of BTC sold (sats)
+    pub sold: u128,
+    /// Amount of ETH (wei) or tokens (base units) bought
+    pub bought: u128,
+    /// Lockup time of BTC in HTLC (number of blocks)
+    pub lockup_btc: u16,
+    /// The seller's Bitcoin address's HASH_160'd pubkey for timeout claim
+    pub seller_pubkey_hash: [u8; 20],
+    /// The seller's ETH redemption address
+    pub seller_eth_address: [u8; 20],
+    /// The preimage-hash, the seller keeps the preimage
     pub preimage_hash: [u8; 32],
+    /// The SHA256 hash of the offer request's cbor bytes
+    pub request_hash: [u8; 32],
+}
+
+/// New terms proposed for an offer request, by its taker or, in reply to the taker, by its maker
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OfferCounter {
+    /// The SHA256 hash of the offer request's cbor bytes
+    pub request_hash: [u8; 32],
+    /// Round of the negotiation, the taker sends the odd rounds starting from 1 and the maker the even ones
+    pub round: u32,
+    /// Amount of BTC sold (sats)
+    pub sold: u128,
+    /// Amount of ETH (wei) or tokens (base units) bought
+    pub bought: u128,
+    /// Lockup time of BTC in HTLC (number of blocks)
+    pub lockup_btc: u16,
+    /// Blocks that ETH will be locked up for
+    pub lockup_eth: u32,
+    /// The sender's pubkey HASH_160'd
+    pub sender_pubkey_hash: [u8; 20],
+    /// The sender's ETH address
+    pub sender_eth_address: [u8; 20],
+}
+
+/// Agreement to the terms of a round of negotiation, sent by the side that didn't propose them
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct CounterAccept {
+    /// The SHA256 hash of the offer request's cbor bytes
+    pub request_hash: [u8; 32],
+    /// The round being agreed to
+    pub round: u32,
+    /// The sender's pubkey HASH_160'd
+    pub sender_pubkey_hash: [u8; 20],
+    /// The sender's ETH address
+    pub sender_eth_address: [u8; 20],
+}
+
+/// The negotiation over an offer request, with every counter-offer in the order it was made
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Negotiation {
+    pub request: OfferRequest,
+    pub counters: Vec<OfferCounter>,
+    /// The round both sides agreed to, once the negotiation has become an [Offer]
+    pub agreed: Option<u32>,
+}
+
+impl Negotiation {
+    /// The taker's first counter-offer, which says who the taker is
+    pub fn taker(&self) -> Option<&OfferCounter> {
+        self.counters.first()
+    }
+
+    pub fn latest(&self) -> Option<&OfferCounter> {
+        self.counters.last()
+    }
+}
+
+/// Withdraws an offer request before it's responded to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct OfferCancel {
+    /// The SHA256 hash of the cancelled offer request's cbor bytes
+    pub request_hash: [u8; 32],
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
@@ -27,32 +252,42 @@ pub struct OfferResponse {
     pub version: u8,
     /// Amount of BTC sold (sats)
     pub sold: u128,
-    /// Amount of ETH bought (wei)
+    /// Amount of ETH (wei) or tokens (base units) bought
     pub bought: u128,
     /// Blocks that ETH will be locked up for
-    pub lockup_eth: u8,
+    pub lockup_eth: u32,
     /// The buyer's pubkey HASH_160'd
     pub buyer_pubkey_hash: [u8; 20],
     /// The buyer's ETH address
     pub buyer_eth_address: [u8; 20],
     /// The SHA256 hash of the offer request's cbor bytes
     pub request_hash: [u8; 32],
+    /// The ERC-20 token bought instead of native ETH, if any
+    #[serde(default)]
+    pub token_address: Option<[u8; 20]>,
+    /// Decimals of the bought asset
+    #[serde(default = "eth_decimals")]
+    pub token_decimals: u8,
+    /// The ETH is locked in the GauloiRelayFactory, so the seller can claim it through a relayer
+    #[serde(default)]
+    pub relayed: bool,
 }
 
 
-/// This is mostly for storage on CLI, basically the same as the offer request + response
+/// This is mostly for storage on CLI, basically the same as the offer request + response. Whichever side created
+/// the offer, the seller sells the BTC and holds the preimage, and the buyer commits the ETH
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Offer {
     /// Version the request / response agreement protocol for backwards incompatibility (potentially)
     pub version: u8,
     /// Amount of BTC sold (sats)
     pub sold: u128,
-    /// Amount of ETH bought (wei)
+    /// Amount of ETH (wei) or tokens (base units) bought
     pub bought: u128,
     /// Blocks that ETH will be locked up for
-    pub lockup_eth: u8,
+    pub lockup_eth: u32,
     /// Blocks that BTC will be locked up for
-    pub lockup_btc: u8,
+    pub lockup_btc: u16,
     /// The seller's pubkey HASH_160'd
     pub seller_pubkey_hash: [u8; 20],
     /// The buyer's pubkey HASH_160'd
@@ -67,10 +302,59 @@ pub struct Offer {
     pub preimage_hash: [u8; 32],
     /// The offer request hash, to use as a "unique" lookup locally for checking pre-images etc
     pub request_hash: [u8;32],
+    /// The ERC-20 token bought instead of native ETH, if any
+    #[serde(default)]
+    pub token_address: Option<[u8; 20]>,
+    /// Decimals of the bought asset
+    #[serde(default = "eth_decimals")]
+    pub token_decimals: u8,
+    /// The ETH is locked in the GauloiRelayFactory, so the seller can claim it through a relayer
+    #[serde(default)]
+    pub relayed: bool,
+    /// Rebuilt from the chain by `scan-eth` after losing the swap storage, only the ETH side of the swap is known
+    #[serde(default)]
+    pub recovered: bool,
+    /// State of the ETH side as of the last `scan-eth`
+    #[serde(default)]
+    pub eth_status: Option<EthSwapStatus>,
+    /// The offer was created by the ETH side
+    #[serde(default)]
+    pub eth_initiated: bool,
+}
+
+/// What can be done with the ETH side of a swap, from our point of view
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthSwapStatus {
+    /// Committed and waiting to be claimed before the timeout
+    Locked,
+    /// Committed to us, claimable with the preimage
+    Claimable,
+    /// We committed it and the timeout has passed without a claim
+    Refundable,
+    /// Claimed with the preimage
+    Claimed,
+    /// Refunded to the committer after the timeout
+    Refunded,
+    /// Committed to us but the timeout passed before we claimed it, only the committer can take it now
+    Expired,
 }
 
 impl Offer {
 
+    /// The ERC-20 token being swapped for, [None] for native ETH
+    pub fn token(&self) -> Option<H160> {
+        self.token_address.map(H160::from)
+    }
+
+    /// The factory the bought asset is locked in
+    pub fn contract(&self) -> SwapContract {
+        match self.token() {
+            Some(token) => SwapContract::Token(token),
+            None if self.relayed => SwapContract::Relay,
+            None => SwapContract::Gauloi,
+        }
+    }
+
     pub fn is_user_seller(&self, user_pubkey_hash_or_eth_address: [u8;20]) -> bool {
         self.seller_pubkey_hash == user_pubkey_hash_or_eth_address
             || self.seller_eth_address == user_pubkey_hash_or_eth_address
@@ -121,3 +405,69 @@ pub struct EthCommitment {
     pub swap_id_hex: String,
 }
 
+
+/// What a locally sent ETH transaction is for, so a stuck one can be matched to its swap
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EthTxKind {
+    /// `createSwap` committing our ETH
+    Commit,
+    /// `claimSwap` revealing the preimage
+    Claim,
+    /// `claimTimeout` reclaiming our ETH after the swap timed out
+    Refund,
+    /// ERC-20 `approve` letting the token factory lock our tokens
+    Approve,
+    /// A 0-value self-send replacing a stuck transaction
+    Cancel,
+}
+
+/// An ETH transaction we've sent that hasn't been seen mined yet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingEthTx {
+    /// The account the transaction was sent from, nonces are only unique per account
+    #[serde(default)]
+    pub from: [u8; 20],
+    /// The nonce the transaction (and any replacements) were sent with
+    pub nonce: u64,
+    /// What the transaction is for
+    pub kind: EthTxKind,
+    /// The preimage-hash of the swap the transaction belongs to
+    pub preimage_hash: [u8; 32],
+    /// Hashes of every transaction sent with this nonce, the latest replacement last
+    pub tx_hashes: Vec<[u8; 32]>,
+    /// JSON of the latest transaction sent, to resend with higher fees
+    pub tx_json: String,
+}
+
+/// Preimage of [Offer::sample]
+#[cfg(test)]
+pub const SAMPLE_PREIMAGE: [u8; 32] = [7; 32];
+
+#[cfg(test)]
+impl Offer {
+    /// 1 BTC for 20 ETH between two made-up parties, for tests
+    pub fn sample() -> Offer {
+        use bitcoin::hashes::{sha256, Hash};
+
+        Offer {
+            version: VERSION,
+            sold: 100_000_000,
+            bought: 20_000_000_000_000_000_000,
+            lockup_eth: 3600,
+            lockup_btc: 144,
+            seller_pubkey_hash: [1; 20],
+            buyer_pubkey_hash: [2; 20],
+            seller_eth_address: [3; 20],
+            buyer_eth_address: [4; 20],
+            swap_id_hex: None,
+            preimage_hash: sha256::Hash::hash(&SAMPLE_PREIMAGE).to_byte_array(),
+            request_hash: [5; 32],
+            token_address: None,
+            token_decimals: ETH_DECIMALS,
+            relayed: false,
+            recovered: false,
+            eth_status: None,
+            eth_initiated: false,
+        }
+    }
+}
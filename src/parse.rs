@@ -1,28 +1,69 @@
-use bitcoin::hashes::Hash;
-use bitcoin::hashes::sha256;
-use ciborium::from_reader;
-use ciborium::into_writer;
+use bitcoin::hashes::{sha256, Hash};
 use clap::Parser;
-use ethers::types::U256;
-use ethers::utils::{hex, format_ether};
+use ethers::types::{H160, U256};
+use ethers::utils::hex;
+use thiserror::Error;
 use yansi::Paint;
 
-use crate::offer::{OfferRequest, OfferResponse, VERSION};
+use crate::cancel::{self, OfferCancelledError};
+use crate::encoding;
+use crate::envelope::{self, SignedEnvelope};
+use crate::expiry;
+use crate::message::{self, Message};
+use crate::offer::{format_bought, EthOfferRequest, EthOfferResponse, OfferRequest, OfferResponse, OfferStatus, VERSION};
+use crate::price;
+use crate::qr::QrArgs;
 use crate::state::GauloiState;
+use crate::timelock::{self, Lockup};
 
-/// Parse the response for an offer we initiated
+/// Parse an offer from the other side of a swap, accepting it prints the response to send back. Cancellations of
+/// offers are parsed too, dropping the offer
 #[derive(Parser, Debug)]
 pub struct ParseOfferArgs {
-    /// Hex encoded offer
+    /// The offer or its cancellation, or the offer's hex from older clients
     offer: String,
+
+    /// Accept the offer even if its rate is further off the price feed than allowed
+    #[clap(long)]
+    ignore_price: bool,
+
+    /// Accept an offer that isn't signed by its seller
+    #[clap(long)]
+    allow_unsigned: bool,
+
+    /// Sign the response with our ETH key as well as our BTC key
+    #[clap(long)]
+    sign_eth: bool,
+
+    /// BTC to fill of a fillable offer, all of it if not given
+    #[clap(long)]
+    fill: Option<f64>,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+}
+
+#[derive(Error, Debug)]
+#[error("A fill has to be between {min} and {max}")]
+pub struct FillOutOfRangeError {
+    pub min: bitcoin::Amount,
+    pub max: bitcoin::Amount,
 }
 
 pub async fn parse_offer(
     state: &mut GauloiState,
     args: ParseOfferArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bytes = hex::decode(args.offer)?;
-    let offer: OfferRequest = from_reader(bytes.as_slice())?;
+    let (bytes, signed) = envelope::open(encoding::decode_as(&args.offer, &["request", "cancel"])?);
+    match Message::decode(&bytes)? {
+        Message::EthRequest(request) => return accept_eth_offer(state, args, request, message::body_hash(&bytes)?, signed).await,
+        Message::Cancel(cancel) => return Ok(cancel::drop_cancelled(state, cancel, signed, args.allow_unsigned)?),
+        _ => {}
+    }
+    let (offer, request_hash) = message::decode_request(&bytes)?;
+    if state.db.get_offer_status(&request_hash)? == Some(OfferStatus::Cancelled) {
+        return Err(OfferCancelledError {}.into());
+    }
 
     let seller_is_us = offer.seller_pubkey_hash == state.btc_address()?.pubkey_hash().to_byte_array();
     if seller_is_us {
@@ -30,24 +71,59 @@ pub async fn parse_offer(
         return Ok(());
     }
     
+    envelope::check_sender(state, signed.as_ref(), offer.seller_pubkey_hash, offer.seller_eth_address, args.allow_unsigned)?;
+    cancel::check_unmatched(state, &request_hash, offer.seller_pubkey_hash, offer.seller_eth_address)?;
+    expiry::check(&state.btc_api, offer.expiry)?;
+
+    // a fillable offer can be taken in part, at its rate
+    let (sold, bought) = match offer.min_fill {
+        Some(min_fill) => {
+            let fill = fill_amount(state, &args, &offer, min_fill)?;
+            (fill, offer.bought_for(fill))
+        }
+        None => (offer.sold, offer.bought),
+    };
+
     let eth_address = &state.eth_address()?;
 
-    let eth_balance = state.eth_api.get_balance(&eth_address).await?;
+    let token = offer.token_address.map(H160::from);
+    // the amounts are shown and checked in the offer's decimals, so they have to be the token's
+    let asset = match token {
+        Some(token) => state.eth_api.checked_token_symbol(token, offer.token_decimals).await?,
+        None => "ETH".to_string(),
+    };
+
+    let eth_balance = state.eth_api.get_balance_of(eth_address, token).await?;
 
-    let offer_u256 = U256::from(offer.bought);
+    let offer_u256 = U256::from(bought);
 
     if eth_balance < offer_u256 {
         println!(
             "Insufficient balance to execute this swap, we have {}, but offer is for {}",
-            Paint::red(eth_balance),
-            Paint::red(offer_u256),
+            Paint::red(format_bought(eth_balance.as_u128(), offer.token_decimals)),
+            Paint::red(format_bought(bought, offer.token_decimals)),
         );
         return Ok(());
     }
 
     println!();
     println!("{}", Paint::yellow("=== Trade Offer ==="));
-    println!("Sell {}ETH to receive {}?", format_ether(offer_u256), bitcoin::Amount::from_sat(offer.sold as u64));
+    if let Some(token) = token {
+        println!("Token: {} ({})", asset, hex::encode_prefixed(token));
+    }
+    if offer.relayed {
+        // we'll have to lock the ETH in the relay factory, so make sure there is one before agreeing to it
+        println!("ETH is locked in the GauloiRelayFactory at {}", hex::encode_prefixed(state.eth_api.relay_gauloi_address()?));
+    }
+    price::check_offer_rate(state, sold, bought, offer.token_decimals, token, args.ignore_price).await?;
+    if let Some(expiry) = offer.expiry {
+        println!("Offer expires at {}", expiry);
+    }
+    println!("BTC is locked up for {} Bitcoin blocks (about {})", offer.lockup_btc, timelock::format_duration(timelock::btc_duration(offer.lockup_btc)));
+    if offer.min_fill.is_some() {
+        println!("Filling {} of the {} offered", bitcoin::Amount::from_sat(sold as u64), bitcoin::Amount::from_sat(offer.sold as u64));
+    }
+    println!("Sell {}{} to receive {}?", format_bought(bought, offer.token_decimals), asset, bitcoin::Amount::from_sat(sold as u64));
     let input = state.editor.readline("[Y]/n?")?;
     if !input.is_empty() && input.to_lowercase().contains("n") {
         // Exit early
@@ -55,37 +131,172 @@ pub async fn parse_offer(
         return Ok(());
     }
 
-    let entered = state.editor.readline(format!("Enter lockup time in Eth blocks [default: {}]: ", offer.lockup_btc).as_str())?;
+    let default_eth = timelock::default_eth_for(offer.lockup_btc);
+    let entered = state.editor.readline(format!(
+        "Enter lockup time, e.g. 12h, or in Eth blocks [default: {} blocks, about {}]: ",
+        default_eth,
+        timelock::format_duration(timelock::eth_duration(default_eth))
+    ).as_str())?;
     let lockup_eth = if entered.is_empty() {
-        offer.lockup_btc
+        default_eth
     } else {
-        entered.parse()?
+        entered.parse::<Lockup>()?.eth_blocks()
     };
+    timelock::check(offer.lockup_btc, lockup_eth)?;
 
     println!("Adding offer...");
 
     state.db.add_pending_offer(offer)?;
 
-    let request_hash = sha256::Hash::hash(bytes.as_slice());
-
     let my_pubkey_hash = state.btc_address()?.pubkey_hash();
     let my_eth_address = state.eth_address()?;
 
     let response = OfferResponse {
         version: VERSION,
-        sold: offer.sold,
-        bought: offer.bought,
-        lockup_eth: lockup_eth,
+        sold,
+        bought,
+        lockup_eth,
         buyer_pubkey_hash: my_pubkey_hash.to_byte_array(),
         buyer_eth_address: my_eth_address.to_fixed_bytes(),
-        request_hash: request_hash.to_byte_array(),
+        request_hash,
+        token_address: offer.token_address,
+        token_decimals: offer.token_decimals,
+        relayed: offer.relayed,
     };
 
-    state.db.add_offer_response(response)?;
+    // a fill's swap needs the preimage-hash the maker makes for it when it confirms the fill, until then keep the
+    // response so the confirmation can be checked against what we asked for
+    if offer.min_fill.is_none() {
+        state.db.add_offer_response(response)?;
+    } else {
+        state.db.put_fill_response(&message::request_hash(&response)?, &response)?;
+    }
 
-    let mut bytes = Vec::new();
-    into_writer(&response, &mut bytes)?;
-    println!("hex for response:\n{}", hex::encode(bytes));
+    let message = Message::Response(response);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "response").await?;
+    if offer.min_fill.is_some() {
+        println!("Import the maker's confirmation of the fill to set up the swap");
+    }
     Ok(())
 }
 
+/// The BTC to fill of a fillable offer, from `--fill` or entered at a prompt
+fn fill_amount(state: &mut GauloiState, args: &ParseOfferArgs, offer: &OfferRequest, min_fill: u128) -> Result<u128, Box<dyn std::error::Error>> {
+    let min = bitcoin::Amount::from_sat(min_fill as u64);
+    let max = bitcoin::Amount::from_sat(offer.sold as u64);
+    let fill = match args.fill {
+        Some(fill) => bitcoin::Amount::from_btc(fill)?,
+        None => {
+            let entered = state.editor.readline(format!("Enter amount of BTC to fill, {} to {} [default: all]: ", min, max).as_str())?;
+            if entered.is_empty() {
+                max
+            } else {
+                bitcoin::Amount::from_btc(entered.parse()?)?
+            }
+        }
+    };
+    if fill < min || fill > max {
+        return Err(FillOutOfRangeError { min, max }.into());
+    }
+    Ok(fill.to_sat() as u128)
+}
+
+
+/// Accept an offer from the ETH side, making the preimage as we'll be selling the BTC
+async fn accept_eth_offer(
+    state: &mut GauloiState,
+    args: ParseOfferArgs,
+    offer: EthOfferRequest,
+    request_hash: [u8; 32],
+    signed: Option<SignedEnvelope>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if offer.buyer_pubkey_hash == state.our_pubkey_hash()? {
+        println!("\nThis is an offer you created!\n");
+        return Ok(());
+    }
+
+    envelope::check_sender(state, signed.as_ref(), offer.buyer_pubkey_hash, offer.buyer_eth_address, args.allow_unsigned)?;
+    cancel::check_unmatched(state, &request_hash, offer.buyer_pubkey_hash, offer.buyer_eth_address)?;
+    expiry::check(&state.btc_api, offer.expiry)?;
+    if state.db.get_offer_status(&request_hash)? == Some(OfferStatus::Cancelled) {
+        return Err(OfferCancelledError {}.into());
+    }
+
+    let token = offer.token_address.map(H160::from);
+    // the amounts are shown and checked in the offer's decimals, so they have to be the token's
+    let asset = match token {
+        Some(token) => state.eth_api.checked_token_symbol(token, offer.token_decimals).await?,
+        None => "ETH".to_string(),
+    };
+
+    let sold = bitcoin::Amount::from_sat(offer.sold as u64);
+    let btc_balance = state.our_btc_balance()?;
+    if let Some(balance) = btc_balance {
+        let balance = bitcoin::Amount::from_sat(balance as u64);
+        if balance < sold {
+            println!(
+                "Insufficient balance to execute this swap, we have {}, but offer is for {}",
+                Paint::red(balance),
+                Paint::red(sold),
+            );
+            return Ok(());
+        }
+    }
+
+    println!();
+    println!("{}", Paint::yellow("=== Trade Offer ==="));
+    if let Some(token) = token {
+        println!("Token: {} ({})", asset, hex::encode_prefixed(token));
+    }
+    if offer.relayed {
+        println!("ETH is locked in the GauloiRelayFactory at {}", hex::encode_prefixed(state.eth_api.relay_gauloi_address()?));
+    }
+    price::check_offer_rate(state, offer.sold, offer.bought, offer.token_decimals, token, args.ignore_price).await?;
+    if let Some(expiry) = offer.expiry {
+        println!("Offer expires at {}", expiry);
+    }
+    println!("ETH is locked up for {} Eth blocks (about {})", offer.lockup_eth, timelock::format_duration(timelock::eth_duration(offer.lockup_eth)));
+    println!("Sell {} to receive {}{}?", sold, format_bought(offer.bought, offer.token_decimals), asset);
+    let input = state.editor.readline("[Y]/n?")?;
+    if !input.is_empty() && input.to_lowercase().contains("n") {
+        // Exit early
+        println!("{}", Paint::red("Not importing trade offer, exiting"));
+        return Ok(());
+    }
+
+    let default_btc = timelock::default_btc_for(offer.lockup_eth)?;
+    let entered = state.editor.readline(format!(
+        "Enter lockup time, e.g. 24h, or in Bitcoin blocks [default: {} blocks, about {}]: ",
+        default_btc,
+        timelock::format_duration(timelock::btc_duration(default_btc))
+    ).as_str())?;
+    let lockup_btc = if entered.is_empty() {
+        default_btc
+    } else {
+        entered.parse::<Lockup>()?.btc_blocks()?
+    };
+    timelock::check(lockup_btc, offer.lockup_eth)?;
+
+    println!("Adding offer...");
+
+    state.db.add_pending_eth_offer(&request_hash, &offer)?;
+    let preimage = GauloiState::preimage();
+    state.db.add_preimage_for(&request_hash, preimage)?;
+
+    let response = EthOfferResponse {
+        version: VERSION,
+        sold: offer.sold,
+        bought: offer.bought,
+        lockup_btc,
+        seller_pubkey_hash: state.our_pubkey_hash()?,
+        seller_eth_address: state.eth_address()?.to_fixed_bytes(),
+        preimage_hash: sha256::Hash::hash(&preimage).to_byte_array(),
+        request_hash,
+    };
+
+    state.db.add_eth_offer(&offer, &response)?;
+
+    let message = Message::EthResponse(response);
+    SignedEnvelope::publish(state, &message, args.sign_eth, &args.qr, "response").await?;
+    Ok(())
+}
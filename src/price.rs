@@ -0,0 +1,111 @@
+//! Sanity checks for offer rates against an on-chain price feed, so a mispriced offer isn't accepted by mistake
+
+use anyhow::Result;
+use ethers::types::H160;
+use thiserror::Error;
+use yansi::Paint;
+
+use crate::offer::format_bought;
+use crate::state::GauloiState;
+
+#[derive(Error, Debug)]
+#[error("Offer rate is {deviation:.1}% off the price feed, more than the {max_deviation}% allowed, pass --ignore-price to go ahead anyway")]
+pub struct PriceDeviationError {
+    pub deviation: f64,
+    pub max_deviation: f64,
+}
+
+/// Print the rate an offer implies and compare it with the configured BTC price feed, failing if it's further off
+/// than the configured deviation unless `ignore_price` is set. Only native ETH offers can be checked
+pub async fn check_offer_rate(
+    state: &GauloiState,
+    sold: u128,
+    bought: u128,
+    decimals: u8,
+    token: Option<H160>,
+    ignore_price: bool,
+) -> Result<()> {
+    let rate = offer_rate(sold, bought, decimals)?;
+    let asset = if token.is_some() { "tokens" } else { "ETH" };
+    println!("Offer rate: {:.6} {} per BTC", rate, asset);
+
+    let Some(feed) = state.config.eth.btc_price_feed else {
+        println!("{} no BTC price feed configured, the rate isn't checked", Paint::yellow("Warning:"));
+        return Ok(());
+    };
+    if token.is_some() {
+        println!("{} the price feed only prices ETH, the token rate isn't checked", Paint::yellow("Warning:"));
+        return Ok(());
+    }
+
+    let feed_rate = state.eth_api.feed_price(feed).await?;
+    let deviation = deviation(rate, feed_rate);
+    let max_deviation = state.config.eth.max_price_deviation;
+    let shown = format!("{:.1}%", deviation);
+    println!(
+        "Price feed rate: {:.6} ETH per BTC, offer is {} off",
+        feed_rate,
+        if deviation > max_deviation { Paint::red(shown) } else { Paint::green(shown) }
+    );
+
+    check_deviation(deviation, max_deviation, ignore_price)
+}
+
+/// The ETH or token units an offer pays per BTC
+fn offer_rate(sold: u128, bought: u128, decimals: u8) -> Result<f64> {
+    let sold_btc = bitcoin::Amount::from_sat(sold as u64).to_btc();
+    let bought_units: f64 = format_bought(bought, decimals).parse()?;
+    Ok(bought_units / sold_btc)
+}
+
+/// How far, in percent, a rate is off the price feed's
+fn deviation(rate: f64, feed_rate: f64) -> f64 {
+    (rate - feed_rate).abs() / feed_rate * 100.0
+}
+
+/// Fail if the deviation is more than allowed, only warning about it if `ignore_price` is set
+fn check_deviation(deviation: f64, max_deviation: f64, ignore_price: bool) -> Result<()> {
+    if deviation > max_deviation {
+        if ignore_price {
+            println!("{} going ahead with the rate anyway", Paint::yellow("Warning:"));
+        } else {
+            return Err(PriceDeviationError { deviation, max_deviation }.into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offer_rate_is_in_whole_units_per_btc() {
+        // 0.5 BTC for 10 ETH
+        assert_eq!(offer_rate(50_000_000, 10_000_000_000_000_000_000, 18).unwrap(), 20.0);
+        // 2 BTC for 130000 of a 6 decimal token
+        assert_eq!(offer_rate(200_000_000, 130_000_000_000, 6).unwrap(), 65_000.0);
+    }
+
+    #[test]
+    fn rate_within_the_allowed_deviation_is_accepted() {
+        let deviation = deviation(20.5, 20.0);
+        assert!((deviation - 2.5).abs() < 1e-9);
+        assert!(check_deviation(deviation, 3.0, false).is_ok());
+        // below the feed counts the same as above it
+        assert!(check_deviation(super::deviation(19.5, 20.0), 3.0, false).is_ok());
+    }
+
+    #[test]
+    fn rate_beyond_the_allowed_deviation_is_refused() {
+        let err = check_deviation(deviation(16.0, 20.0), 3.0, false).unwrap_err();
+        let err = err.downcast::<PriceDeviationError>().unwrap();
+        assert!((err.deviation - 20.0).abs() < 1e-9);
+        assert_eq!(err.max_deviation, 3.0);
+    }
+
+    #[test]
+    fn rate_beyond_the_allowed_deviation_goes_through_when_ignored() {
+        assert!(check_deviation(deviation(16.0, 20.0), 3.0, true).is_ok());
+    }
+}
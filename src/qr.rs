@@ -0,0 +1,67 @@
+//! QR codes of messages and payment URIs, so they can be moved between devices without copying long strings
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Args;
+use image::Luma;
+use qrcode::render::{svg, unicode};
+use qrcode::QrCode;
+
+/// Options for showing a command's output as a QR code
+#[derive(Args, Debug)]
+pub struct QrArgs {
+    /// Print a QR code of the output in the terminal
+    #[clap(long)]
+    pub qr: bool,
+
+    /// Write a QR code of the output to a .png or .svg file
+    #[clap(long)]
+    pub qr_file: Option<PathBuf>,
+}
+
+/// Show `data` as a QR code in the terminal and / or a file, as asked for in `args`
+pub fn output(args: &QrArgs, data: &str) -> Result<()> {
+    if !args.qr && args.qr_file.is_none() {
+        return Ok(());
+    }
+    let code = QrCode::new(data.as_bytes())?;
+
+    if args.qr {
+        // inverted so the code reads on a dark terminal background
+        let rendered = code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build();
+        println!("{}", rendered);
+    }
+
+    if let Some(path) = &args.qr_file {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("png") => code.render::<Luma<u8>>().min_dimensions(300, 300).build().save(path)?,
+            Some("svg") => {
+                let rendered = code.render::<svg::Color>().min_dimensions(300, 300).build();
+                std::fs::write(path, rendered)?;
+            }
+            _ => bail!("QR files can only be written as .png or .svg"),
+        }
+        println!("QR code written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// [output] a bech32m encoded message, uppercased as bech32 allows so the QR code can use its denser alphanumeric mode
+pub fn output_message(args: &QrArgs, encoded: &str) -> Result<()> {
+    output(args, &encoded.to_uppercase())
+}
+
+/// A BIP21 URI paying `amount` to `address`
+pub fn bip21_uri(address: &bitcoin::Address, amount: bitcoin::Amount, label: &str) -> String {
+    format!(
+        "bitcoin:{}?amount={}&label={}",
+        address,
+        amount.to_string_in(bitcoin::Denomination::Bitcoin),
+        label.replace(' ', "%20")
+    )
+}
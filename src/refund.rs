@@ -0,0 +1,36 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::execute::ensure_nothing_pending;
+use crate::offer::EthTxKind;
+use crate::state::GauloiState;
+
+/// Reclaim the ETH we committed to a swap that timed out without being claimed
+#[derive(Parser, Debug)]
+pub struct RefundArgs {
+    swap_id: usize,
+
+    /// Print the refund unsigned for the offline signer instead of sending it, see `eth-broadcast`
+    #[clap(long)]
+    offline: bool,
+}
+
+pub async fn refund(state: &mut GauloiState, args: RefundArgs) -> Result<()> {
+    println!();
+    let offer = state.db.get_complete_offer(args.swap_id)?;
+    if !offer.is_user_buyer(state.our_pubkey_hash()?) {
+        bail!("We didn't commit the ETH for this swap, there's nothing to refund")
+    }
+
+    let swap_id = state.eth_api.our_swap_id(offer.preimage_hash, offer.contract()).await?;
+    if swap_id.is_zero() {
+        bail!("No ETH commitment found for this swap")
+    }
+
+    ensure_nothing_pending(state, &offer, EthTxKind::Refund).await?;
+    let sender = state.eth_sender(args.offline)?;
+    println!("Reclaiming ETH from swap {}...", swap_id);
+    state.eth_api.refund_eth(sender, &state.db, swap_id, offer.contract()).await?;
+    println!("ETH refunded successfully");
+    Ok(())
+}
@@ -0,0 +1,146 @@
+//! A small HTTP relayer that submits BTC sellers' signed claims on GauloiRelayFactory swaps, paid out of the swap
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use ethers::types::{Address, H256};
+use ethers::utils::parse_ether;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use yansi::Paint;
+
+use crate::eth_api::RelayedClaim;
+use crate::state::GauloiState;
+
+/// Longest we wait for a client to send its whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request line and headers we read, a claim only needs a few short headers
+const MAX_HEAD_SIZE: u64 = 8 * 1024;
+
+/// Largest request body we read, a signed claim is well under this
+const MAX_BODY_SIZE: usize = 4 * 1024;
+
+/// Run a relayer that claims GauloiRelayFactory swaps for BTC sellers with our ETH account, until Ctrl-C
+#[derive(Parser, Debug)]
+pub struct RelayArgs {
+    /// Address to listen for claims on
+    #[clap(short, long, default_value = "127.0.0.1:8645")]
+    listen: SocketAddr,
+
+    /// Smallest fee in ETH we'll submit a claim for, claims also have to cover their gas
+    #[clap(short, long, default_value = "0")]
+    min_fee: String,
+}
+
+/// Reply to `GET /address`, the relayer address sellers sign their claims for
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RelayerInfo {
+    pub address: Address,
+}
+
+/// Reply to `POST /claim` with a [RelayedClaim]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RelayerResponse {
+    Claimed { tx_hash: Option<H256> },
+    Failed { error: String },
+}
+
+pub async fn relay(state: &mut GauloiState, args: RelayArgs) -> Result<()> {
+    println!();
+    let min_fee = parse_ether(&args.min_fee)?;
+    let relay_gauloi_address = state.eth_api.relay_gauloi_address()?;
+    let listener = TcpListener::bind(args.listen).await?;
+    println!(
+        "Relaying claims for {:?} on {} as {:?}, Ctrl-C to stop",
+        relay_gauloi_address, Paint::green(args.listen), state.eth_address()?
+    );
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        // claims are handled one at a time so they don't race each other for our nonce
+        if let Err(e) = handle_request(state, stream, min_fee).await {
+            println!("{} request from {} failed: {}", Paint::yellow("Warning:"), peer, e);
+        }
+    }
+    println!("Relayer stopped");
+    Ok(())
+}
+
+async fn handle_request(state: &GauloiState, stream: TcpStream, min_fee: ethers::types::U256) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    // a client that never finishes its request would hold up every claim behind it
+    let (request_line, body) = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut reader)).await {
+        Ok(request) => request?,
+        Err(_) => bail!("timed out after {}s reading the request", READ_TIMEOUT.as_secs()),
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let (status, reply) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/address")) => ("200 OK", serde_json::to_vec(&RelayerInfo { address: state.eth_address()? })?),
+        (Some("POST"), Some("/claim")) => {
+            let response = match serde_json::from_slice::<RelayedClaim>(&body) {
+                Ok(claim) => {
+                    println!("Relaying claim for swap {} with a fee of {} wei...", claim.swap_id, claim.fee);
+                    match state.eth_api.submit_relayed_claim(state.get_wallet()?, &state.db, &claim, min_fee).await {
+                        Ok(receipt) => RelayerResponse::Claimed { tx_hash: receipt.map(|receipt| receipt.transaction_hash) },
+                        Err(e) => RelayerResponse::Failed { error: e.to_string() },
+                    }
+                }
+                Err(e) => RelayerResponse::Failed { error: format!("invalid claim: {}", e) },
+            };
+            ("200 OK", serde_json::to_vec(&response)?)
+        }
+        _ => ("404 Not Found", Vec::new()),
+    };
+
+    let stream = reader.get_mut();
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, reply.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&reply).await?;
+    stream.flush().await?;
+    if status != "200 OK" {
+        bail!("{} {}", status, request_line.trim_end());
+    }
+    Ok(())
+}
+
+/// Read the request line and body, refusing anything bigger than a claim needs
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Result<(String, Vec<u8>)> {
+    let mut head = (&mut *reader).take(MAX_HEAD_SIZE);
+    let mut request_line = String::new();
+    head.read_line(&mut request_line).await?;
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header).await? == 0 {
+            bail!("request headers are over {} bytes or cut short", MAX_HEAD_SIZE);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        bail!("request body of {} bytes is over the {} byte limit", content_length, MAX_BODY_SIZE);
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok((request_line, body))
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use bitcoin::hashes::{sha256, Hash};
+use clap::Parser;
+use ethers::types::{Address, U256};
+use yansi::Paint;
+
+use crate::eth_api::{ScannedSwap, SwapContract};
+use crate::offer::{self, EthSwapStatus, Offer};
+use crate::state::GauloiState;
+
+/// Find ETH swaps on chain that involve our ETH address, rebuilding or updating the matching offers
+#[derive(Parser, Debug)]
+pub struct ScanEthArgs {
+
+}
+
+pub async fn scan_eth(state: &mut GauloiState, _args: ScanEthArgs) -> Result<()> {
+    println!();
+    let mut addresses = vec![state.eth_address()?];
+    if let Some(offline_signer) = state.config.eth.offline_signer {
+        if !addresses.contains(&offline_signer) {
+            addresses.push(offline_signer);
+        }
+    }
+
+    let tip = state.eth_api.tip_height().await?;
+    let offers = state.db.get_all_offers()?;
+    let mut updated = 0;
+    let mut recovered = 0;
+
+    for address in addresses {
+        println!("Scanning swaps involving {:?}...", address);
+        for scanned in state.eth_api.scan_swaps(address).await? {
+            let status = eth_status(&scanned, address, U256::from(tip.as_u64()));
+            let existing = offers.iter().find(|offer| offer.preimage_hash == scanned.swap.preimage_hash);
+            let offer = match existing {
+                Some(offer) => {
+                    updated += 1;
+                    Offer {
+                        swap_id_hex: Some(format!("{:x}", scanned.swap_id)),
+                        eth_status: Some(status),
+                        ..offer.clone()
+                    }
+                }
+                None => {
+                    // the contract doesn't cap the value, but our offers only hold 128 bits of it
+                    let Ok(bought) = u128::try_from(scanned.swap.value) else {
+                        println!(
+                            "{} swap {} ({:?}) is for {} wei, too much to recover, skipping it",
+                            Paint::yellow("Warning:"), scanned.swap_id, scanned.contract, scanned.swap.value
+                        );
+                        continue;
+                    };
+                    recovered += 1;
+                    recover_offer(state, &scanned, address, bought, status).await?
+                }
+            };
+            println!(
+                "Swap {} ({:?}): {} to {:?}, {:?}",
+                scanned.swap_id,
+                scanned.contract,
+                offer::format_bought(offer.bought, offer.token_decimals),
+                scanned.swap.buyer,
+                Paint::green(status),
+            );
+            state.db.put_offer(&offer)?;
+        }
+    }
+
+    println!();
+    println!("Updated {} offers, recovered {} from the chain", updated, recovered);
+    Ok(())
+}
+
+/// Work out what we can do with a swap as of the `tip` block
+fn eth_status(scanned: &ScannedSwap, us: Address, tip: U256) -> EthSwapStatus {
+    let swap = &scanned.swap;
+    if swap.complete {
+        if swap.preimage != [0u8; 32] {
+            EthSwapStatus::Claimed
+        } else {
+            EthSwapStatus::Refunded
+        }
+    } else if swap.unlock_block <= tip {
+        if swap.seller == us {
+            EthSwapStatus::Refundable
+        } else {
+            EthSwapStatus::Expired
+        }
+    } else if swap.buyer == us {
+        EthSwapStatus::Claimable
+    } else {
+        EthSwapStatus::Locked
+    }
+}
+
+/// Rebuild an offer from the ETH side of a swap, the BTC side was only in the lost storage so the counterparty's
+/// pubkey hash, the BTC amount and lockups are left empty
+async fn recover_offer(state: &GauloiState, scanned: &ScannedSwap, us: Address, bought: u128, status: EthSwapStatus) -> Result<Offer> {
+    let swap = &scanned.swap;
+    let our_pubkey_hash = state.our_pubkey_hash()?;
+    let (token_address, token_decimals) = match scanned.contract {
+        SwapContract::Token(token) => (Some(token.to_fixed_bytes()), state.eth_api.token_info(token).await?.1),
+        _ => (None, offer::ETH_DECIMALS),
+    };
+
+    // there's no request to hash, so key the offer by where the swap is on chain instead
+    let factory = state.eth_api.factory_address(scanned.contract)?;
+    let mut key = factory.as_bytes().to_vec();
+    key.extend_from_slice(&<[u8; 32]>::from(scanned.swap_id));
+
+    Ok(Offer {
+        version: offer::VERSION,
+        sold: 0,
+        bought,
+        lockup_eth: 0,
+        lockup_btc: 0,
+        // the contract's buyer receives the ETH, so sold the BTC
+        seller_pubkey_hash: if swap.buyer == us { our_pubkey_hash } else { [0u8; 20] },
+        buyer_pubkey_hash: if swap.seller == us { our_pubkey_hash } else { [0u8; 20] },
+        seller_eth_address: swap.buyer.to_fixed_bytes(),
+        buyer_eth_address: swap.seller.to_fixed_bytes(),
+        swap_id_hex: Some(format!("{:x}", scanned.swap_id)),
+        preimage_hash: swap.preimage_hash,
+        request_hash: sha256::Hash::hash(&key).to_byte_array(),
+        token_address,
+        token_decimals,
+        relayed: scanned.contract == SwapContract::Relay,
+        recovered: true,
+        eth_status: Some(status),
+        // who created the offer was only in the lost storage too
+        eth_initiated: false,
+    })
+}
@@ -1,21 +1,19 @@
 use std::str::FromStr;
-use std::sync::Arc;
 
 use bitcoin::bip32::{DerivationPath, ExtendedPrivKey};
-use bitcoin::hashes::{sha256, Hash};
-use bitcoin::opcodes::all::*;
-use bitcoin::{PublicKey, Network, ScriptBuf, Script, Address, address};
-use bitcoin::secp256k1::{All, Secp256k1 as BtcSecp};
+use bitcoin::hashes::Hash;
+use bitcoin::{PublicKey, Network, Address};
+use bitcoin::secp256k1::{All, Secp256k1 as BtcSecp, SecretKey};
 use ethers::prelude::*;
-use ethers::prelude::k256::{Secp256k1, ecdsa::SigningKey};
+use ethers::prelude::k256::ecdsa::SigningKey;
 use rand::RngCore;
 use rand::prelude::ThreadRng;
 use rustyline::DefaultEditor;
 
 use crate::bitcoin_api::BitcoinApi;
-use crate::eth_api::EthApi;
+use crate::config::GauloiConfig;
+use crate::eth_api::{EthApi, EthSender};
 use crate::swaps::SwapStorage;
-use crate::offer::Offer;
 
 pub struct GauloiState {
     pub secp: BtcSecp<All>,
@@ -25,6 +23,9 @@ pub struct GauloiState {
     pub btc_api: BitcoinApi,
     pub eth_api: EthApi,
     pub db: SwapStorage,
+    pub config: GauloiConfig,
+    /// ETH account imported from a keystore, used instead of the hot wallet derived one if set
+    pub eth_signer: Option<Wallet<SigningKey>>,
 }
 
 impl GauloiState {
@@ -38,6 +39,12 @@ impl GauloiState {
         Ok(PublicKey::from_private_key(&self.secp, &derivation.to_priv()))
     }
 
+    /// The key behind [GauloiState::btc_address], used to sign our offers and responses
+    pub fn btc_signing_key(&self) -> Result<SecretKey, anyhow::Error> {
+        let derivation = &self.master_extended.derive_priv(&self.secp, &GauloiState::btc_derivation()?)?;
+        Ok(derivation.private_key)
+    }
+
     pub fn our_pubkey_hash(&self) -> Result<[u8;20], anyhow::Error> {
         let btc_address = self.btc_address()?;
         Ok(btc_address.pubkey_hash().to_byte_array())
@@ -48,11 +55,33 @@ impl GauloiState {
         Ok(Address::p2wpkh(&address, self.network)?)
     }
 
+    /// Our BTC balance, or [None] if it couldn't be fetched
+    pub fn our_btc_balance(&self) -> Result<Option<u128>, anyhow::Error> {
+        // only the balance is kept, the fetch's error type can't be held across the awaits that follow it
+        Ok(self.btc_api.get_balance(&self.p2wpkh_address()?).ok())
+    }
+
     pub fn get_wallet(&self) -> Result<Wallet<SigningKey>, anyhow::Error> {
+        if let Some(wallet) = &self.eth_signer {
+            return Ok(wallet.clone());
+        }
         let signer = SigningKey::from_slice(&self.master_extended.private_key.secret_bytes())?;
         Ok(Wallet::from(signer))
     }
 
+    /// Who sends our swap transactions, the air-gapped [crate::config::EthChainConfig::offline_signer] if they're
+    /// to be signed offline and the wallet otherwise
+    pub fn eth_sender(&self, offline: bool) -> Result<EthSender, anyhow::Error> {
+        if offline {
+            let address = match self.config.eth.offline_signer {
+                Some(address) => address,
+                None => self.eth_address()?,
+            };
+            return Ok(EthSender::Offline(address));
+        }
+        Ok(EthSender::Wallet(self.get_wallet()?))
+    }
+
     pub fn eth_address(&self) -> Result<H160, anyhow::Error> {
         let wallet = self.get_wallet()?;
         Ok(wallet.address())
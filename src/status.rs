@@ -0,0 +1,82 @@
+use anyhow::Result;
+use bitcoin::Amount;
+use clap::Parser;
+use yansi::Paint;
+
+use crate::offer::format_bought;
+use crate::qr::{self, QrArgs};
+use crate::state::GauloiState;
+use crate::timelock;
+
+/// Show where a swap is at, with a BIP21 URI for funding its BTC HTLC
+#[derive(Parser, Debug)]
+pub struct StatusArgs {
+    swap_id: usize,
+
+    #[clap(flatten)]
+    qr: QrArgs,
+}
+
+pub async fn status(state: &mut GauloiState, args: StatusArgs) -> Result<()> {
+    println!();
+    let offer = state.db.get_complete_offer(args.swap_id)?;
+    let our_pubkey_hash = state.our_pubkey_hash()?;
+    let asset = match offer.token() {
+        Some(token) => state.eth_api.token_info(token).await?.0,
+        None => "ETH".to_string(),
+    };
+
+    println!("{}", Paint::yellow(format!("=== Swap {} ===", args.swap_id)));
+    println!(
+        "Sell {} for {}{}",
+        Amount::from_sat(offer.sold as u64),
+        format_bought(offer.bought, offer.token_decimals),
+        asset
+    );
+    if offer.is_user_seller(our_pubkey_hash) {
+        println!("We sell the BTC, lock up: {} BTC blocks", offer.lockup_btc);
+    } else if offer.is_user_buyer(our_pubkey_hash) {
+        println!("We sell the {}, lock up: {} ETH blocks", asset, offer.lockup_eth);
+    }
+    println!(
+        "Lockups: about {} for the BTC, {} for the {}",
+        timelock::format_duration(timelock::btc_duration(offer.lockup_btc)),
+        timelock::format_duration(timelock::eth_duration(offer.lockup_eth)),
+        asset
+    );
+    if !offer.recovered {
+        if let Err(e) = timelock::check(offer.lockup_btc, offer.lockup_eth) {
+            println!("{} {}, `execute` won't commit to it", Paint::yellow("Warning:"), e);
+        }
+    }
+
+    match &offer.swap_id_hex {
+        Some(swap_id) => println!("ETH swap: {} ({:?})", swap_id, offer.contract()),
+        None => println!("ETH swap: not committed yet"),
+    }
+    if let Some(eth_status) = offer.eth_status {
+        println!("ETH side as of the last scan: {:?}", eth_status);
+    }
+    if offer.recovered {
+        println!("Only the ETH side was recovered from the chain, the BTC HTLC isn't known");
+        return Ok(());
+    }
+
+    let htlc_address = bitcoin::Address::p2wsh(&offer.htlc_script(), state.network);
+    let sold = Amount::from_sat(offer.sold as u64);
+    let funded = state.btc_api.get_balance(&htlc_address).ok().map(|balance| Amount::from_sat(balance as u64));
+    println!("BTC HTLC: {}", htlc_address);
+    match funded {
+        Some(funded) if funded >= sold => println!("HTLC funded with {}", Paint::green(funded)),
+        Some(funded) => println!("HTLC funded with {} of {}", Paint::red(funded), sold),
+        None => println!("{} couldn't look up the HTLC balance", Paint::yellow("Warning:")),
+    }
+
+    let remaining = funded.map(|funded| sold.checked_sub(funded).unwrap_or(Amount::ZERO)).unwrap_or(sold);
+    if remaining > Amount::ZERO {
+        let uri = qr::bip21_uri(&htlc_address, remaining, &format!("gauloi swap {}", args.swap_id));
+        println!("Fund the HTLC with:\n{}", uri);
+        qr::output(&args.qr, &uri)?;
+    }
+    Ok(())
+}
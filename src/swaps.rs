@@ -1,3 +1,6 @@
+use std::path::Path;
+use std::sync::Arc;
+
 use anyhow::{bail, Result};
 use bitcoin::hashes::{Hash, sha256};
 use redb::{Database, ReadableTable, TableDefinition};
@@ -7,21 +10,67 @@ use crate::offer::{self, *};
 const TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("swap_data");
 const PREIMAGE_TABLE: TableDefinition<&[u8], &[u8;32]> = TableDefinition::new("preimages");
 const OFFER_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("full_swaps");
+const PENDING_ETH_TX_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("pending_eth_txs_by_account");
+const UNSIGNED_ETH_TX_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("unsigned_eth_txs_by_account");
+/// Tables from before ETH transactions were keyed by their account as well as their nonce
+const LEGACY_PENDING_ETH_TX_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("pending_eth_txs");
+const LEGACY_UNSIGNED_ETH_TX_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("unsigned_eth_txs");
+const ETH_OFFER_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("eth_swap_data");
+const NEGOTIATION_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("negotiations");
+const FILL_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("offer_fills");
+const FILL_RESPONSE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("fill_responses");
+const OFFER_STATUS_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("offer_status");
+const UNMATCHED_CANCEL_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("unmatched_cancels");
 
+/// Cloning shares the same database, so background tasks can use the storage alongside the shell
+#[derive(Clone)]
 pub struct SwapStorage {
-    db: Database,
+    db: Arc<Database>,
 }
 
 impl Default for SwapStorage {
     fn default() -> Self {
-        let db = Database::create("swaps.db").expect("Couldn't create swap storage");
-        SwapStorage {
-            db
-        }
+        SwapStorage::open("swaps.db").expect("Couldn't open swap storage")
+    }
+}
+
+/// Key of an ETH transaction in the pending and unsigned tables, its account followed by its nonce
+fn eth_tx_key(from: [u8; 20], nonce: u64) -> [u8; 28] {
+    let mut key = [0u8; 28];
+    key[..20].copy_from_slice(&from);
+    key[20..].copy_from_slice(&nonce.to_be_bytes());
+    key
+}
+
+/// The `from` of a transaction stored as JSON, for records from before the account was stored alongside it
+fn legacy_tx_from(tx_json: &str) -> [u8; 20] {
+    let tx: serde_json::Value = serde_json::from_str(tx_json).unwrap_or_default();
+    let from = tx["from"].as_str().unwrap_or_default().trim_start_matches("0x");
+    let mut address = [0u8; 20];
+    if hex::decode_to_slice(from, &mut address).is_err() {
+        return [0u8; 20];
     }
+    address
 }
 
 impl SwapStorage {
+    /// Open the swap storage at `path`, creating it and its tables if they don't exist yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        let storage = SwapStorage {
+            db: Arc::new(Database::create(path)?),
+        };
+        storage.create_tables()?;
+        storage.migrate_legacy_eth_txs()?;
+        Ok(storage)
+    }
+
+    /// A fresh storage in the temp directory, for tests
+    #[cfg(test)]
+    pub fn temporary(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("gauloi-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        SwapStorage::open(path).unwrap()
+    }
     
     pub fn get_all_offer_requests(&self) -> Result<Vec<OfferRequest>, anyhow::Error> {
         let read_tx = self.db.begin_read()?;
@@ -53,7 +102,7 @@ impl SwapStorage {
         if let Some((i, _)) = found {
             return Ok(Some(i));
         }
-        return Ok(None);
+        Ok(None)
     }
 
     pub fn get_complete_offer(&self, index: usize) -> Result<Offer> {
@@ -93,6 +142,140 @@ impl SwapStorage {
         }
     }
     
+    /// Every pending offer request with the hash it's stored under
+    pub fn get_pending_offers(&self) -> Result<Vec<([u8; 32], OfferRequest)>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(TABLE)?;
+        let mut pending = Vec::new();
+        for next in table.iter()? {
+            let (k, v) = next?;
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(k.value());
+            pending.push((hash, ciborium::from_reader(v.value())?));
+        }
+        Ok(pending)
+    }
+
+    /// Whether a full offer has been stored for the request hash, i.e. the request has been responded to
+    pub fn has_offer(&self, request_hash: &[u8]) -> Result<bool, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(OFFER_TABLE)?;
+        let found = table.get(request_hash)?.is_some();
+        Ok(found)
+    }
+
+    pub fn set_offer_status(&self, request_hash: &[u8], status: OfferStatus) -> Result<(), anyhow::Error> {
+        let mut writer: Vec<u8> = Vec::new();
+        ciborium::into_writer(&status, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(OFFER_STATUS_TABLE)?;
+            write_table.insert(request_hash, writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_offer_status(&self, request_hash: &[u8]) -> Result<Option<OfferStatus>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(OFFER_STATUS_TABLE)?;
+        let value = table.get(request_hash)?;
+        let status = match value {
+            Some(v) => Some(ciborium::from_reader(v.value())?),
+            None => None,
+        };
+        Ok(status)
+    }
+
+    /// Keep the signed cancellation of an offer we haven't seen yet under the offer's request hash
+    pub fn put_unmatched_cancel(&self, request_hash: &[u8; 32], envelope: &[u8]) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(UNMATCHED_CANCEL_TABLE)?;
+            write_table.insert(request_hash.as_slice(), envelope)?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove and return the cancellation kept for an offer, if one arrived before it
+    pub fn take_unmatched_cancel(&self, request_hash: &[u8; 32]) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        let envelope = {
+            let mut table = write_tx.open_table(UNMATCHED_CANCEL_TABLE)?;
+            let envelope = table.remove(request_hash.as_slice())?.map(|v| v.value().to_vec());
+            envelope
+        };
+        write_tx.commit()?;
+        Ok(envelope)
+    }
+
+    /// Store an offer created by the ETH side under its request hash
+    pub fn add_pending_eth_offer(&self, request_hash: &[u8; 32], offer: &EthOfferRequest) -> Result<(), anyhow::Error> {
+        let mut writer: Vec<u8> = Vec::new();
+        ciborium::into_writer(offer, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(ETH_OFFER_TABLE)?;
+            write_table.insert(request_hash.as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    /// Every pending offer created by the ETH side with the hash it's stored under
+    pub fn get_pending_eth_offers(&self) -> Result<Vec<([u8; 32], EthOfferRequest)>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(ETH_OFFER_TABLE)?;
+        let mut pending = Vec::new();
+        for next in table.iter()? {
+            let (k, v) = next?;
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(k.value());
+            pending.push((hash, ciborium::from_reader(v.value())?));
+        }
+        Ok(pending)
+    }
+
+    pub fn get_pending_eth_offer(&self, offer_hash: &[u8]) -> Result<Option<EthOfferRequest>, anyhow::Error> {
+        let pending = self.get_pending_eth_offers()?;
+        Ok(pending.into_iter().find(|(hash, _)| hash.as_slice() == offer_hash).map(|(_, offer)| offer))
+    }
+
+    /// Store the full offer for an ETH side offer and the BTC side's acceptance of it
+    pub fn add_eth_offer(&self, request: &EthOfferRequest, response: &EthOfferResponse) -> Result<Offer, anyhow::Error> {
+        let complete_offer = Offer {
+            version: offer::VERSION,
+            sold: request.sold,
+            bought: request.bought,
+            lockup_eth: request.lockup_eth,
+            lockup_btc: response.lockup_btc,
+            seller_pubkey_hash: response.seller_pubkey_hash,
+            buyer_pubkey_hash: request.buyer_pubkey_hash,
+            seller_eth_address: response.seller_eth_address,
+            buyer_eth_address: request.buyer_eth_address,
+            swap_id_hex: None,
+            request_hash: response.request_hash,
+            preimage_hash: response.preimage_hash,
+            token_address: request.token_address,
+            token_decimals: request.token_decimals,
+            relayed: request.relayed,
+            recovered: false,
+            eth_status: None,
+            eth_initiated: true,
+        };
+        self.put_offer(&complete_offer)?;
+        Ok(complete_offer)
+    }
+
+    /// Complete an offer we created on the ETH side with the BTC side's acceptance of it
+    pub fn add_eth_offer_response(&self, response: &EthOfferResponse) -> Result<Offer, anyhow::Error> {
+        match self.get_pending_eth_offer(response.request_hash.as_slice())? {
+            Some(request) => self.add_eth_offer(&request, response),
+            None => bail!("No offer / request locally"),
+        }
+    }
+
     pub fn add_preimage(&self, offer: OfferRequest, preimage: [u8;32]) -> Result<(), anyhow::Error> {
         let mut writer: Vec<u8> = Vec::new();
         ciborium::into_writer(&offer, &mut writer)?;
@@ -108,6 +291,17 @@ impl SwapStorage {
         Ok(())
     }
     
+    /// Store the preimage for an offer by its request hash, for offers we didn't create the request for
+    pub fn add_preimage_for(&self, request_hash: &[u8; 32], preimage: [u8;32]) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(PREIMAGE_TABLE)?;
+            write_table.insert(request_hash.as_slice(), &preimage)?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
     pub fn get_preimage(&self, offer_hash: &[u8]) -> Result<Option<[u8;32]>, anyhow::Error> {
         let read_tx = self.db.begin_read()?;
         let table = read_tx.open_table(PREIMAGE_TABLE)?;
@@ -122,7 +316,177 @@ impl SwapStorage {
         }
     }
     
+    /// Store how much of a fillable offer is left under the offer's request hash
+    pub fn put_fill_state(&self, request_hash: &[u8; 32], fill_state: &FillState) -> Result<(), anyhow::Error> {
+        let mut writer = Vec::new();
+        ciborium::into_writer(fill_state, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(FILL_TABLE)?;
+            write_table.insert(request_hash.as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_fill_state(&self, request_hash: &[u8; 32]) -> Result<Option<FillState>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(FILL_TABLE)?;
+        let value = table.get(request_hash.as_slice())?;
+        let fill_state = match value {
+            Some(v) => Some(ciborium::from_reader(v.value())?),
+            None => None,
+        };
+        Ok(fill_state)
+    }
+
+    /// Store our response taking a fill of someone else's fillable offer, under the fill's hash
+    pub fn put_fill_response(&self, fill_hash: &[u8; 32], response: &OfferResponse) -> Result<(), anyhow::Error> {
+        let mut writer = Vec::new();
+        ciborium::into_writer(response, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(FILL_RESPONSE_TABLE)?;
+            write_table.insert(fill_hash.as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_fill_response(&self, fill_hash: &[u8; 32]) -> Result<Option<OfferResponse>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(FILL_RESPONSE_TABLE)?;
+        let value = table.get(fill_hash.as_slice())?;
+        let response = match value {
+            Some(v) => Some(ciborium::from_reader(v.value())?),
+            None => None,
+        };
+        Ok(response)
+    }
+
+    /// Store the swap for a confirmed fill of a fillable offer, under the fill's hash
+    pub fn add_fill(&self, request: &OfferRequest, fill: &FillConfirm) -> Result<Offer, anyhow::Error> {
+        let complete_offer = Offer {
+            version: offer::VERSION,
+            sold: fill.sold,
+            bought: fill.bought,
+            lockup_eth: fill.lockup_eth,
+            lockup_btc: request.lockup_btc,
+            seller_pubkey_hash: request.seller_pubkey_hash,
+            buyer_pubkey_hash: fill.buyer_pubkey_hash,
+            seller_eth_address: request.seller_eth_address,
+            buyer_eth_address: fill.buyer_eth_address,
+            swap_id_hex: None,
+            request_hash: fill.fill_hash,
+            preimage_hash: fill.preimage_hash,
+            token_address: request.token_address,
+            token_decimals: request.token_decimals,
+            relayed: request.relayed,
+            recovered: false,
+            eth_status: None,
+            eth_initiated: false,
+        };
+        self.put_offer(&complete_offer)?;
+        Ok(complete_offer)
+    }
+
+    /// Store the negotiation over an offer request under the request's hash, replacing the one that's there
+    pub fn put_negotiation(&self, request_hash: &[u8; 32], negotiation: &Negotiation) -> Result<(), anyhow::Error> {
+        let mut writer = Vec::new();
+        ciborium::into_writer(negotiation, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(NEGOTIATION_TABLE)?;
+            write_table.insert(request_hash.as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_negotiation(&self, request_hash: &[u8; 32]) -> Result<Option<Negotiation>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(NEGOTIATION_TABLE)?;
+        let value = table.get(request_hash.as_slice())?;
+        let negotiation = match value {
+            Some(v) => Some(ciborium::from_reader(v.value())?),
+            None => None,
+        };
+        Ok(negotiation)
+    }
+
+    /// Drop the negotiation over an offer request, returning whether there was one
+    pub fn remove_negotiation(&self, request_hash: &[u8; 32]) -> Result<bool, anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_tx.open_table(NEGOTIATION_TABLE)?;
+            let removed = table.remove(request_hash.as_slice())?.is_some();
+            removed
+        };
+        write_tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Turn the terms of an agreed round of negotiation into the full offer
+    pub fn agree_negotiation(&self, request_hash: &[u8; 32], round: u32) -> Result<Offer, anyhow::Error> {
+        let Some(mut negotiation) = self.get_negotiation(request_hash)? else {
+            bail!("No negotiation for this offer locally")
+        };
+        // the offer's preimage hash can only go into one swap
+        if self.has_offer(request_hash)? {
+            bail!("This offer has already been taken by a response, it can't also be agreed in a negotiation")
+        }
+        let (Some(taker), Some(agreed)) = (negotiation.taker(), round.checked_sub(1).and_then(|i| negotiation.counters.get(i as usize))) else {
+            bail!("No round {} in the negotiation", round)
+        };
+        let request = negotiation.request;
+        let complete_offer = Offer {
+            version: offer::VERSION,
+            sold: agreed.sold,
+            bought: agreed.bought,
+            lockup_eth: agreed.lockup_eth,
+            lockup_btc: agreed.lockup_btc,
+            seller_pubkey_hash: request.seller_pubkey_hash,
+            buyer_pubkey_hash: taker.sender_pubkey_hash,
+            seller_eth_address: request.seller_eth_address,
+            buyer_eth_address: taker.sender_eth_address,
+            swap_id_hex: None,
+            request_hash: *request_hash,
+            preimage_hash: request.preimage_hash,
+            token_address: request.token_address,
+            token_decimals: request.token_decimals,
+            relayed: request.relayed,
+            recovered: false,
+            eth_status: None,
+            eth_initiated: false,
+        };
+        self.put_offer(&complete_offer)?;
+        negotiation.agreed = Some(round);
+        self.put_negotiation(request_hash, &negotiation)?;
+        Ok(complete_offer)
+    }
+
+    /// Store an offer under its request hash, replacing the one that's there
+    pub fn put_offer(&self, offer: &Offer) -> Result<(), anyhow::Error> {
+        let mut writer = Vec::new();
+        ciborium::into_writer(offer, &mut writer)?;
+
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(OFFER_TABLE)?;
+            write_table.insert(offer.request_hash.as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
     pub fn add_offer_response(&self, response: OfferResponse) -> Result<Offer, anyhow::Error> {
+        // the offer's preimage hash can only go into one swap
+        if self.has_offer(&response.request_hash)? {
+            bail!("This offer has already been taken, its swap is already stored")
+        }
+        if self.get_negotiation(&response.request_hash)?.is_some() {
+            bail!("This offer is being negotiated, it can only be taken by agreeing a counter-offer")
+        }
 
         let pending_offer = self.get_pending_offer(response.request_hash.as_slice())?;
         if let Some(request) = pending_offer {
@@ -139,6 +503,12 @@ impl SwapStorage {
                 swap_id_hex: None,
                 request_hash: response.request_hash,
                 preimage_hash: request.preimage_hash,
+                token_address: request.token_address,
+                token_decimals: request.token_decimals,
+                relayed: request.relayed,
+                recovered: false,
+                eth_status: None,
+                eth_initiated: false,
             };
 
             // cbor the full offer
@@ -157,4 +527,127 @@ impl SwapStorage {
             bail!("No offer / request locally")
         }
     }
-}
\ No newline at end of file
+
+    /// Open every table for writing once, so reads on a fresh database find them empty rather than missing
+    fn create_tables(&self) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        write_tx.open_table(PREIMAGE_TABLE)?;
+        for table in [
+            TABLE, OFFER_TABLE, PENDING_ETH_TX_TABLE, UNSIGNED_ETH_TX_TABLE, ETH_OFFER_TABLE, NEGOTIATION_TABLE,
+            FILL_TABLE, FILL_RESPONSE_TABLE, OFFER_STATUS_TABLE, UNMATCHED_CANCEL_TABLE,
+        ] {
+            write_tx.open_table(table)?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    /// Move ETH transactions from the nonce-keyed tables into the ones keyed by account and nonce, taking the
+    /// account from the stored transaction
+    fn migrate_legacy_eth_txs(&self) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        for (legacy, table) in [
+            (LEGACY_PENDING_ETH_TX_TABLE, PENDING_ETH_TX_TABLE),
+            (LEGACY_UNSIGNED_ETH_TX_TABLE, UNSIGNED_ETH_TX_TABLE),
+        ] {
+            let records = {
+                let legacy_table = write_tx.open_table(legacy)?;
+                let records: Vec<Vec<u8>> = legacy_table.iter()?.map(|next| {
+                    let (_, v) = next.unwrap();
+                    v.value().to_vec()
+                }).collect();
+                records
+            };
+            let mut write_table = write_tx.open_table(table)?;
+            for record in records {
+                let mut tx: PendingEthTx = ciborium::from_reader(record.as_slice())?;
+                tx.from = legacy_tx_from(&tx.tx_json);
+                let mut writer: Vec<u8> = Vec::new();
+                ciborium::into_writer(&tx, &mut writer)?;
+                write_table.insert(eth_tx_key(tx.from, tx.nonce).as_slice(), writer.as_slice())?;
+            }
+            drop(write_table);
+            write_tx.delete_table(legacy)?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn add_pending_eth_tx(&self, pending: &PendingEthTx) -> Result<(), anyhow::Error> {
+        let mut writer: Vec<u8> = Vec::new();
+        ciborium::into_writer(pending, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(PENDING_ETH_TX_TABLE)?;
+            write_table.insert(eth_tx_key(pending.from, pending.nonce).as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_pending_eth_txs(&self) -> Result<Vec<PendingEthTx>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(PENDING_ETH_TX_TABLE)?;
+        let range = table.iter()?;
+        let serialized: Vec<PendingEthTx> = range.map(|next| {
+            let (_, v) = next.unwrap();
+            ciborium::from_reader(v.value()).unwrap()
+        }).collect();
+        Ok(serialized)
+    }
+
+    pub fn get_pending_eth_tx(&self, from: [u8; 20], nonce: u64) -> Result<Option<PendingEthTx>, anyhow::Error> {
+        let pending = self.get_pending_eth_txs()?;
+        Ok(pending.into_iter().find(|tx| tx.from == from && tx.nonce == nonce))
+    }
+
+    /// Find a pending transaction of a given kind for the swap with the preimage-hash
+    pub fn get_pending_eth_tx_for(&self, preimage_hash: [u8; 32], kind: EthTxKind) -> Result<Option<PendingEthTx>, anyhow::Error> {
+        let pending = self.get_pending_eth_txs()?;
+        Ok(pending.into_iter().find(|tx| tx.preimage_hash == preimage_hash && tx.kind == kind))
+    }
+
+    pub fn remove_pending_eth_tx(&self, from: [u8; 20], nonce: u64) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(PENDING_ETH_TX_TABLE)?;
+            write_table.remove(eth_tx_key(from, nonce).as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    /// Keep a transaction that was exported for offline signing so the signed version can be checked against it
+    pub fn add_unsigned_eth_tx(&self, unsigned: &PendingEthTx) -> Result<(), anyhow::Error> {
+        let mut writer: Vec<u8> = Vec::new();
+        ciborium::into_writer(unsigned, &mut writer)?;
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(UNSIGNED_ETH_TX_TABLE)?;
+            write_table.insert(eth_tx_key(unsigned.from, unsigned.nonce).as_slice(), writer.as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_unsigned_eth_tx(&self, from: [u8; 20], nonce: u64) -> Result<Option<PendingEthTx>, anyhow::Error> {
+        let read_tx = self.db.begin_read()?;
+        let table = read_tx.open_table(UNSIGNED_ETH_TX_TABLE)?;
+        let value = table.get(eth_tx_key(from, nonce).as_slice())?;
+        let unsigned = match value {
+            Some(v) => Some(ciborium::from_reader(v.value())?),
+            None => None,
+        };
+        Ok(unsigned)
+    }
+
+    pub fn remove_unsigned_eth_tx(&self, from: [u8; 20], nonce: u64) -> Result<(), anyhow::Error> {
+        let write_tx = self.db.begin_write()?;
+        {
+            let mut write_table = write_tx.open_table(UNSIGNED_ETH_TX_TABLE)?;
+            write_table.remove(eth_tx_key(from, nonce).as_slice())?;
+        }
+        write_tx.commit()?;
+        Ok(())
+    }
+}
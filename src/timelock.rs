@@ -0,0 +1,207 @@
+//! Lockups on the two chains, entered as wall-clock time and converted to each chain's blocks, and checked so the
+//! BTC stays locked long enough after the ETH lockup ends for the ETH side to claim it with the revealed preimage
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// Average seconds per block on each chain, for converting a lockup time to blocks
+pub const BTC_BLOCK_SECS: u64 = 600;
+pub const ETH_BLOCK_SECS: u64 = 12;
+
+/// BTC blocks can come in well under 10 minutes apart for hours at a time, so BTC lockups are counted at this pace
+/// when checking they're safe
+pub const BTC_FAST_BLOCK_SECS: u64 = 450;
+
+/// ETH slots are missed now and then, which stretches a lockup counted in blocks, so ETH lockups are counted at this
+/// pace when checking they're safe
+pub const ETH_SLOW_BLOCK_SECS: u64 = 13;
+
+/// Time the ETH side needs between the ETH lockup ending and the BTC one, to pick up the preimage from the ETH claim
+/// and get its BTC claim confirmed
+pub const SAFETY_MARGIN: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Shortest ETH lockup, so the BTC side has time to claim the ETH once the BTC HTLC is funded
+pub const MIN_ETH_LOCKUP: Duration = Duration::from_secs(60 * 60);
+
+/// BTC lockup used when none is entered
+pub const DEFAULT_BTC_LOCKUP: Lockup = Lockup::Time(Duration::from_secs(24 * 60 * 60));
+
+/// ETH lockup used for ETH-initiated offers when none is entered
+pub const DEFAULT_ETH_LOCKUP: Lockup = Lockup::Time(Duration::from_secs(12 * 60 * 60));
+
+#[derive(Error, Debug)]
+pub enum LockupError {
+    #[error("Couldn't read lockup {0:?}, enter a time like 90m, 24h or 2d, or a number of blocks")]
+    Unparseable(String),
+    #[error("A BTC lockup of {0} is more than the {max} blocks a relative timelock can hold", max = u16::MAX)]
+    BtcTooLong(Lockup),
+    #[error("The ETH lockup of {0} blocks is shorter than the {min} minimum", min = format_duration(MIN_ETH_LOCKUP))]
+    EthTooShort(u32),
+    #[error(
+        "The BTC lockup of {lockup_btc} blocks (at least {btc} if blocks come in fast) doesn't outlast the ETH lockup of {lockup_eth} blocks (up to {eth}) by the {margin} margin the ETH side needs to claim the BTC",
+        btc = fast_btc_time(.lockup_btc),
+        eth = slow_eth_time(.lockup_eth),
+        margin = format_duration(SAFETY_MARGIN)
+    )]
+    Unsafe { lockup_btc: u16, lockup_eth: u32 },
+    #[error(
+        "Only {remaining_btc} blocks of the BTC lockup are left (at least {btc} if blocks come in fast), which doesn't outlast an ETH lockup of {lockup_eth} blocks (up to {eth}) by the {margin} margin, it's too late to go on with the swap",
+        btc = fast_btc_time(.remaining_btc),
+        eth = slow_eth_time(.lockup_eth),
+        margin = format_duration(SAFETY_MARGIN)
+    )]
+    TooLate { remaining_btc: u16, lockup_eth: u32 },
+}
+
+/// A lockup as entered, either a wall-clock time converted to blocks on each chain or a number of blocks taken as is
+#[derive(Debug, Clone, Copy)]
+pub enum Lockup {
+    Time(Duration),
+    Blocks(u32),
+}
+
+impl Lockup {
+    /// Number of BTC blocks for the lockup, which has to fit in a CSV relative timelock
+    pub fn btc_blocks(&self) -> Result<u16, LockupError> {
+        let blocks = match self {
+            Lockup::Time(time) => time.as_secs().div_ceil(BTC_BLOCK_SECS),
+            Lockup::Blocks(blocks) => *blocks as u64,
+        };
+        u16::try_from(blocks).map_err(|_| LockupError::BtcTooLong(*self))
+    }
+
+    /// Number of ETH blocks for the lockup
+    pub fn eth_blocks(&self) -> u32 {
+        match self {
+            Lockup::Time(time) => time.as_secs().div_ceil(ETH_BLOCK_SECS).try_into().unwrap_or(u32::MAX),
+            Lockup::Blocks(blocks) => *blocks,
+        }
+    }
+}
+
+impl FromStr for Lockup {
+    type Err = LockupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entered = s.trim();
+        let unparseable = || LockupError::Unparseable(s.to_string());
+        let (number, unit_secs) = match entered.char_indices().last() {
+            Some((i, 'm')) => (&entered[..i], 60),
+            Some((i, 'h')) => (&entered[..i], 60 * 60),
+            Some((i, 'd')) => (&entered[..i], 24 * 60 * 60),
+            _ => return entered.parse().map(Lockup::Blocks).map_err(|_| unparseable()),
+        };
+        let number: u64 = number.trim().parse().map_err(|_| unparseable())?;
+        let secs = number.checked_mul(unit_secs).ok_or_else(unparseable)?;
+        Ok(Lockup::Time(Duration::from_secs(secs)))
+    }
+}
+
+impl fmt::Display for Lockup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lockup::Time(time) => write!(f, "{}", format_duration(*time)),
+            Lockup::Blocks(blocks) => write!(f, "{} blocks", blocks),
+        }
+    }
+}
+
+/// Format a lockup time in whole hours where it can be, or else in minutes
+pub fn format_duration(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs.is_multiple_of(60 * 60) {
+        format!("{}h", secs / (60 * 60))
+    } else {
+        format!("{}m", secs.div_ceil(60))
+    }
+}
+
+/// Expected time a BTC lockup lasts
+pub fn btc_duration(lockup_btc: u16) -> Duration {
+    Duration::from_secs(lockup_btc as u64 * BTC_BLOCK_SECS)
+}
+
+/// Expected time an ETH lockup lasts
+pub fn eth_duration(lockup_eth: u32) -> Duration {
+    Duration::from_secs(lockup_eth as u64 * ETH_BLOCK_SECS)
+}
+
+fn fast_btc_time(lockup_btc: &u16) -> String {
+    format_duration(btc_duration_fast(*lockup_btc))
+}
+
+fn slow_eth_time(lockup_eth: &u32) -> String {
+    format_duration(eth_duration_slow(*lockup_eth))
+}
+
+fn btc_duration_fast(lockup_btc: u16) -> Duration {
+    Duration::from_secs(lockup_btc as u64 * BTC_FAST_BLOCK_SECS)
+}
+
+fn eth_duration_slow(lockup_eth: u32) -> Duration {
+    Duration::from_secs(lockup_eth as u64 * ETH_SLOW_BLOCK_SECS)
+}
+
+/// Check a pair of lockups is safe for both sides: the ETH one is long enough for the BTC side to claim the ETH in,
+/// and the BTC one outlasts it by [SAFETY_MARGIN] even if BTC blocks come in fast and ETH blocks slow
+pub fn check(lockup_btc: u16, lockup_eth: u32) -> Result<(), LockupError> {
+    if eth_duration(lockup_eth) < MIN_ETH_LOCKUP {
+        return Err(LockupError::EthTooShort(lockup_eth));
+    }
+    if btc_duration_fast(lockup_btc) < eth_duration_slow(lockup_eth) + SAFETY_MARGIN {
+        return Err(LockupError::Unsafe { lockup_btc, lockup_eth });
+    }
+    Ok(())
+}
+
+/// Check what's left of a BTC lockup that's already running still outlasts an ETH lockup from now, by
+/// [SAFETY_MARGIN] even if BTC blocks come in fast and ETH blocks slow
+pub fn check_remaining(remaining_btc: u16, lockup_eth: u32) -> Result<(), LockupError> {
+    if btc_duration_fast(remaining_btc) < eth_duration_slow(lockup_eth) + SAFETY_MARGIN {
+        return Err(LockupError::TooLate { remaining_btc, lockup_eth });
+    }
+    Ok(())
+}
+
+/// Blocks left of a BTC lockup before it can be refunded, counted from the earliest confirmation of the HTLC's
+/// outputs, or from the next block if none have confirmed
+pub fn remaining_btc(lockup_btc: u16, confirmed_at: Option<u32>, tip: u32) -> u16 {
+    let start = confirmed_at.unwrap_or(tip + 1);
+    (start + lockup_btc as u32).saturating_sub(tip + 1).try_into().unwrap_or(lockup_btc)
+}
+
+/// Check a BTC lockup leaves room for at least the shortest ETH lockup, for offers where the ETH one isn't known yet
+pub fn check_btc(lockup_btc: u16) -> Result<(), LockupError> {
+    check(lockup_btc, Lockup::Time(MIN_ETH_LOCKUP).eth_blocks())
+}
+
+/// Check an ETH lockup is long enough, and short enough for a BTC lockup to outlast it, for offers where the BTC one
+/// isn't known yet
+pub fn check_eth(lockup_eth: u32) -> Result<(), LockupError> {
+    let lockup_btc = min_btc_for(lockup_eth)?;
+    check(lockup_btc, lockup_eth)
+}
+
+/// Shortest BTC lockup that safely outlasts an ETH lockup
+pub fn min_btc_for(lockup_eth: u32) -> Result<u16, LockupError> {
+    let needed = eth_duration_slow(lockup_eth) + SAFETY_MARGIN;
+    let blocks = needed.as_secs().div_ceil(BTC_FAST_BLOCK_SECS);
+    u16::try_from(blocks).map_err(|_| LockupError::BtcTooLong(Lockup::Blocks(blocks.try_into().unwrap_or(u32::MAX))))
+}
+
+/// ETH lockup to suggest for a BTC lockup: half as long, or less if that wouldn't leave the safety margin
+pub fn default_eth_for(lockup_btc: u16) -> u32 {
+    let half = Lockup::Time(btc_duration(lockup_btc) / 2).eth_blocks();
+    let longest_safe = btc_duration_fast(lockup_btc)
+        .saturating_sub(SAFETY_MARGIN)
+        .as_secs() / ETH_SLOW_BLOCK_SECS;
+    half.min(longest_safe.try_into().unwrap_or(u32::MAX))
+}
+
+/// BTC lockup to suggest for an ETH lockup: the default, or longer if the ETH lockup needs it
+pub fn default_btc_for(lockup_eth: u32) -> Result<u16, LockupError> {
+    Ok(DEFAULT_BTC_LOCKUP.btc_blocks()?.max(min_btc_for(lockup_eth)?))
+}
@@ -0,0 +1,91 @@
+use clap::{Parser, Subcommand};
+use ethers::types::{H160, H256};
+use yansi::Paint;
+
+use crate::eth_api::PendingTxState;
+use crate::state::GauloiState;
+
+/// Manage ETH transactions that were sent but haven't been mined yet
+#[derive(Parser, Debug)]
+pub struct TxArgs {
+    #[clap(subcommand)]
+    command: TxCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum TxCommand {
+    /// List the pending ETH transactions
+    List,
+    /// Resend a pending transaction with the same nonce and a higher fee
+    Speedup {
+        nonce: u64,
+    },
+    /// Replace a pending transaction with a 0-value send to ourselves
+    Cancel {
+        nonce: u64,
+    },
+}
+
+pub async fn tx(state: &mut GauloiState, args: TxArgs) -> Result<(), anyhow::Error> {
+    println!();
+    match args.command {
+        TxCommand::List => list(state).await,
+        TxCommand::Speedup { nonce } => replace(state, nonce, false).await,
+        TxCommand::Cancel { nonce } => replace(state, nonce, true).await,
+    }
+}
+
+/// Drop pending transactions that were mined or replaced while we weren't looking, printing what happened
+pub async fn reconcile(state: &GauloiState) -> Result<(), anyhow::Error> {
+    let reconciled = state.eth_api.reconcile_pending_txs(&state.db, state.eth_address()?).await?;
+    for (pending, tx_state) in reconciled {
+        match tx_state {
+            PendingTxState::Mined(tx_hash) => println!(
+                "{:?} transaction with nonce {} was mined in {:?}",
+                pending.kind, pending.nonce, tx_hash
+            ),
+            PendingTxState::Replaced => println!(
+                "{:?} transaction with nonce {} was replaced by another transaction",
+                pending.kind, pending.nonce
+            ),
+            PendingTxState::Pending => println!(
+                "{} {:?} transaction with nonce {} is still pending, use `tx speedup {}` or `tx cancel {}`",
+                Paint::yellow("Warning:"), pending.kind, pending.nonce, pending.nonce, pending.nonce
+            ),
+        }
+    }
+    Ok(())
+}
+
+async fn list(state: &mut GauloiState) -> Result<(), anyhow::Error> {
+    reconcile(state).await?;
+    let pending = state.db.get_pending_eth_txs()?;
+    println!("Total pending ETH transactions: {}", pending.len());
+    for tx in pending {
+        let latest = tx.tx_hashes.last().map(|hash| H256::from(*hash)).unwrap_or_default();
+        println!("[nonce {}] {:?} from {:?}, preimage-hash: {}, latest tx: {:?}, sent {} time(s)",
+            tx.nonce,
+            tx.kind,
+            H160::from(tx.from),
+            hex::encode(tx.preimage_hash),
+            latest,
+            tx.tx_hashes.len(),
+        );
+    }
+    Ok(())
+}
+
+async fn replace(state: &mut GauloiState, nonce: u64, cancel: bool) -> Result<(), anyhow::Error> {
+    if cancel {
+        let input = state.editor.readline(format!("Replace the transaction with nonce {} with a 0-value self-send y/[N]? ", nonce).as_str())?;
+        if !input.trim().to_lowercase().starts_with('y') {
+            println!("{}", Paint::red("Not cancelling transaction, exiting"));
+            return Ok(());
+        }
+    }
+
+    let signer = state.get_wallet()?;
+    let tx_hash = state.eth_api.replace_pending_tx(signer, &state.db, nonce, cancel).await?;
+    println!("Sent replacement transaction {:?} with nonce {}", tx_hash, nonce);
+    Ok(())
+}
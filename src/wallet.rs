@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+use clap::{Parser, Subcommand};
+use ethers::prelude::k256::ecdsa::SigningKey;
+use ethers::signers::{Signer, Wallet};
+use ethers::utils::hex;
+use rand::prelude::ThreadRng;
+use yansi::Paint;
+
+use crate::state::GauloiState;
+
+/// Imported keystores are copied next to the hot wallet so the profile doesn't depend on where they came from
+pub const KEYSTORE_PATH: &str = "eth-keystore.json";
+
+/// Manage the keys used by this client
+#[derive(Parser, Debug)]
+pub struct WalletArgs {
+    #[clap(subcommand)]
+    command: WalletCommand,
+}
+
+// the shared prefix is part of the subcommand names, which say which chain's keys they manage
+#[allow(clippy::enum_variant_names)]
+#[derive(Subcommand, Debug)]
+enum WalletCommand {
+    /// Export the current ETH account as an encrypted JSON keystore (Web3 Secret Storage)
+    EthExportKeystore {
+        /// Folder to write the keystore file to
+        #[clap(short, long, default_value = ".")]
+        out: PathBuf,
+    },
+    /// Use the account in an encrypted JSON keystore for the ETH side instead of the hot wallet derived one
+    EthImportKeystore {
+        /// Path to the keystore file
+        path: PathBuf,
+    },
+    /// Go back to using the hot wallet derived ETH account
+    EthClearKeystore,
+}
+
+pub async fn wallet(state: &mut GauloiState, args: WalletArgs) -> Result<(), anyhow::Error> {
+    println!();
+    match args.command {
+        WalletCommand::EthExportKeystore { out } => export_keystore(state, out),
+        WalletCommand::EthImportKeystore { path } => import_keystore(state, path),
+        WalletCommand::EthClearKeystore => clear_keystore(state),
+    }
+}
+
+/// Decrypt the keystore the config points at, asking for its password
+pub fn unlock_keystore(path: &str) -> Result<Wallet<SigningKey>, anyhow::Error> {
+    let password = rpassword::prompt_password(format!("Password for ETH keystore {}: ", path))?;
+    Ok(Wallet::<SigningKey>::decrypt_keystore(path, password)?)
+}
+
+fn export_keystore(state: &mut GauloiState, out: PathBuf) -> Result<(), anyhow::Error> {
+    let wallet = state.get_wallet()?;
+    let password = rpassword::prompt_password("New keystore password: ")?;
+    let confirmation = rpassword::prompt_password("Confirm keystore password: ")?;
+    if password != confirmation {
+        bail!("Passwords don't match")
+    }
+
+    let name = format!("gauloi-{}.json", hex::encode(wallet.address()));
+    let mut rng = ThreadRng::default();
+    Wallet::<SigningKey>::encrypt_keystore(&out, &mut rng, wallet.signer().to_bytes(), password, Some(name.as_str()))?;
+    println!("Exported {} to {}", hex::encode_prefixed(wallet.address()), out.join(name).display());
+    Ok(())
+}
+
+fn import_keystore(state: &mut GauloiState, path: PathBuf) -> Result<(), anyhow::Error> {
+    let password = rpassword::prompt_password("Keystore password: ")?;
+    let wallet = Wallet::<SigningKey>::decrypt_keystore(&path, password)?;
+
+    // Copying a file onto itself truncates it, and a copy cut short would lose the key, so write alongside and rename
+    let in_place = Path::new(KEYSTORE_PATH).exists()
+        && std::fs::canonicalize(&path)? == std::fs::canonicalize(KEYSTORE_PATH)?;
+    if !in_place {
+        let partial = format!("{}.partial", KEYSTORE_PATH);
+        std::fs::copy(&path, &partial)?;
+        std::fs::rename(&partial, KEYSTORE_PATH)?;
+    }
+    state.config.eth.keystore = Some(KEYSTORE_PATH.to_string());
+    state.config.save()?;
+    println!("ETH account is now {}", Paint::green(hex::encode_prefixed(wallet.address())));
+    state.eth_signer = Some(wallet);
+    Ok(())
+}
+
+fn clear_keystore(state: &mut GauloiState) -> Result<(), anyhow::Error> {
+    if state.config.eth.keystore.is_none() {
+        println!("Already using the hot wallet derived ETH account");
+        return Ok(());
+    }
+    state.config.eth.keystore = None;
+    state.config.save()?;
+    state.eth_signer = None;
+    println!("ETH account is now {}", Paint::green(hex::encode_prefixed(state.eth_address()?)));
+    println!("The imported keystore is still in {}, remove it once you no longer need it", KEYSTORE_PATH);
+    Ok(())
+}